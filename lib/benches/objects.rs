@@ -0,0 +1,98 @@
+extern crate criterion;
+extern crate geo;
+extern crate rust_topojson_client;
+extern crate topojson;
+
+use std::fs::File;
+use std::io::Read;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use geo::Geometry;
+use topojson::{Topology, Value};
+
+use rust_topojson_client::decoded::DecodedTopology;
+use rust_topojson_client::feature::feature;
+#[cfg(feature = "rayon")]
+use rust_topojson_client::feature::feature_par;
+
+/// Decodes each country of the 50m world file, one object at a time.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut file = File::open("./tests/world-atlas/world/50m.json")
+        .expect("File did not open.");
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .expect("Could not read file.");
+
+    let topology: Topology =
+        serde_json::from_str(&contents).expect("Failed to read as json.");
+
+    let countries = topology
+        .objects
+        .iter()
+        .find(|o| o.name == "countries")
+        .expect("countries should be present.");
+    let Value::GeometryCollection(countries) = &countries.geometry.value else {
+        panic!("countries should be a GeometryCollection");
+    };
+
+    c.bench_function("objects", |b| {
+        b.iter(|| {
+            for country in countries {
+                let geometry = feature::<f64>(&topology, &country.value);
+                assert!(matches!(
+                    geometry,
+                    Geometry::Polygon(_) | Geometry::MultiPolygon(_)
+                ));
+            }
+        })
+    });
+
+    // Countries contain no points, so they decode the same with either topology.
+    let decoded = DecodedTopology::new(&topology);
+    c.bench_function("objects decoded", |b| {
+        b.iter(|| {
+            for country in countries {
+                let geometry = feature::<f64>(&decoded, &country.value);
+                assert!(matches!(
+                    geometry,
+                    Geometry::Polygon(_) | Geometry::MultiPolygon(_)
+                ));
+            }
+        })
+    });
+}
+
+/// Decodes the countries of the 50m world file as one collection, on one
+/// thread and then on the rayon thread pool.
+#[cfg(feature = "rayon")]
+pub fn par_benchmark(c: &mut Criterion) {
+    let mut file = File::open("./tests/world-atlas/world/50m.json")
+        .expect("File did not open.");
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .expect("Could not read file.");
+
+    let topology: Topology =
+        serde_json::from_str(&contents).expect("Failed to read as json.");
+
+    let countries = &topology
+        .objects
+        .iter()
+        .find(|o| o.name == "countries")
+        .expect("countries should be present.")
+        .geometry
+        .value;
+
+    c.bench_function("collection", |b| {
+        b.iter(|| feature::<f64>(&topology, countries))
+    });
+    c.bench_function("collection par", |b| {
+        b.iter(|| feature_par::<f64>(&topology, countries))
+    });
+}
+
+#[cfg(not(feature = "rayon"))]
+criterion_group!(benches, criterion_benchmark);
+#[cfg(feature = "rayon")]
+criterion_group!(benches, criterion_benchmark, par_benchmark);
+criterion_main!(benches);
@@ -1,43 +1,66 @@
-use topojson::{NamedGeometry, Topology, Value};
+use topojson::{Arc, Topology, Value};
 
-use crate::transform::gen_transform;
 use crate::transform::Transform;
+use crate::translate;
 
-fn bbox(topology: &Topology) -> [f64; 4] {
-    let mut state = BBox {
-        t: gen_transform(&topology.transform),
-        x0: f64::INFINITY,
-        y0: f64::INFINITY,
-        x1: f64::NEG_INFINITY,
-        y1: f64::NEG_INFINITY,
-    };
-
-    for arc in &topology.arcs {
-        for (i, a) in arc.iter().enumerate() {
-            let p = (state.t)(a, i);
-            if p[0] < state.x0 {
-                state.x0 = p[0];
-            }
-            if p[0] > state.x1 {
-                state.x1 = p[0];
-            }
-            if p[1] < state.y0 {
-                state.y0 = p[1];
-            }
-            if p[1] > state.y1 {
-                state.y1 = p[1];
-            }
-        }
+/// Computes the bounding box of the topology, `[x0, y0, x1, y1]`.
+///
+/// Any existing bbox is ignored. All arcs and all point geometries are
+/// considered.
+///
+/// None: -
+///   * The topology has no arcs and no points.
+#[must_use]
+pub fn bbox(topology: &Topology) -> Option<[f64; 4]> {
+    let mut state = BBox::new(topology);
+
+    for i in 0..topology.arcs.len() {
+        state.bbox_arc(i);
     }
 
-    for key in &topology.objects {
-        state.bbox_geometry(key);
+    for o in &topology.objects {
+        state.bbox_geometry(&o.geometry);
     }
 
-    [state.x0, state.y0, state.x1, state.y1]
+    state.finish()
+}
+
+/// Given a object name, computes the bounding box of that object.
+///
+/// Only the arcs referenced by the object are considered.
+///
+/// None: -
+///   * The object subsection does not contain the name.
+///   * The object is empty.
+#[must_use]
+pub fn object_bbox(topology: &Topology, name: &str) -> Option<[f64; 4]> {
+    topology
+        .objects
+        .iter()
+        .find(|x| x.name == name)
+        .and_then(|ng| geometry_bbox(topology, &ng.geometry))
+}
+
+/// Computes the bounding box of a geometry, `[x0, y0, x1, y1]`.
+///
+/// Only the arcs referenced by the geometry are considered.
+///
+/// None: -
+///   * The geometry is empty.
+#[must_use]
+pub fn geometry_bbox(
+    topology: &Topology,
+    o: &topojson::Geometry,
+) -> Option<[f64; 4]> {
+    let mut state = BBox::new(topology);
+    state.bbox_geometry(o);
+    state.finish()
 }
 
-struct BBox {
+struct BBox<'a> {
+    arcs: &'a [Arc],
+    // Arcs shared by several geometries are only decoded once.
+    seen: Vec<bool>,
     t: Transform,
     x0: f64,
     y0: f64,
@@ -45,9 +68,28 @@ struct BBox {
     y1: f64,
 }
 
-impl BBox {
-    fn bbox_point(&mut self, p: &[f64]) {
-        let p = (self.t)(p, 0);
+impl<'a> BBox<'a> {
+    fn new(topology: &'a Topology) -> Self {
+        Self {
+            arcs: &topology.arcs,
+            seen: vec![false; topology.arcs.len()],
+            t: Transform::new(topology.transform.as_ref()),
+            x0: f64::INFINITY,
+            y0: f64::INFINITY,
+            x1: f64::NEG_INFINITY,
+            y1: f64::NEG_INFINITY,
+        }
+    }
+
+    fn finish(self) -> Option<[f64; 4]> {
+        if self.x0 <= self.x1 && self.y0 <= self.y1 {
+            Some([self.x0, self.y0, self.x1, self.y1])
+        } else {
+            None
+        }
+    }
+
+    fn extend(&mut self, p: &[f64]) {
         if p[0] < self.x0 {
             self.x0 = p[0];
         }
@@ -62,14 +104,38 @@ impl BBox {
         }
     }
 
-    fn bbox_geometry(&mut self, o: &NamedGeometry) {
-        match &o.geometry.value {
+    fn bbox_point(&mut self, p: &[f64]) {
+        let p = self.t.point(p);
+        self.extend(&p);
+    }
+
+    /// Decode the arc, skipping arcs which have already been considered.
+    fn bbox_arc(&mut self, index: usize) {
+        let Some(arc) = self.arcs.get(index) else {
+            return;
+        };
+        if self.seen[index] {
+            return;
+        }
+        self.seen[index] = true;
+        let mut d = self.t.decoder();
+        for a in arc {
+            let p = d.decode(a);
+            self.extend(&p);
+        }
+    }
+
+    fn bbox_line(&mut self, arcs: &[i32]) {
+        for arc in arcs {
+            self.bbox_arc(translate(*arc));
+        }
+    }
+
+    fn bbox_geometry(&mut self, o: &topojson::Geometry) {
+        match &o.value {
             Value::GeometryCollection(vg) => {
                 for g in vg {
-                    self.bbox_geometry(&NamedGeometry {
-                        name: "i".to_string(),
-                        geometry: g.clone(),
-                    });
+                    self.bbox_geometry(g);
                 }
             }
             Value::Point(p) => {
@@ -80,8 +146,18 @@ impl BBox {
                     self.bbox_point(p);
                 }
             }
-            _ => {
-                // unimplemented!("Can I skip this?");
+            Value::LineString(arcs) => self.bbox_line(arcs),
+            Value::MultiLineString(arcs) | Value::Polygon(arcs) => {
+                for ring in arcs {
+                    self.bbox_line(ring);
+                }
+            }
+            Value::MultiPolygon(mp) => {
+                for polygon in mp {
+                    for ring in polygon {
+                        self.bbox_line(ring);
+                    }
+                }
             }
         }
     }
@@ -94,6 +170,7 @@ mod bbox_tests {
     use std::io::Read;
 
     use pretty_assertions::assert_eq;
+    use topojson::NamedGeometry;
     use topojson::Topology;
 
     use super::*;
@@ -111,18 +188,15 @@ mod bbox_tests {
                 transform: None,
                 foreign_members: None,
             }),
-            [
-                f64::INFINITY,
-                f64::INFINITY,
-                f64::NEG_INFINITY,
-                f64::NEG_INFINITY
-            ]
+            None
         );
     }
 
     #[test]
     fn computes_for_quantized_topology() {
-        println!("topojson.bbox(topology) computes the bbox for a quantized topology, if missing");
+        println!(
+            "topojson.bbox(topology) computes the bbox for a quantized topology, if missing"
+        );
         let mut file = File::open("./tests/topojson/polygon-q1e4.json")
             .expect("Could not load json file.");
         let mut data = String::new();
@@ -131,7 +205,7 @@ mod bbox_tests {
 
         let topology: Topology =
             serde_json::from_str(&data).expect("Did not parse correctly.");
-        assert_eq!(bbox(&topology), [0_f64, 0_f64, 10_f64, 10_f64]);
+        assert_eq!(bbox(&topology), Some([0_f64, 0_f64, 10_f64, 10_f64]));
     }
 
     #[test]
@@ -147,7 +221,7 @@ mod bbox_tests {
 
         let topology: Topology =
             serde_json::from_str(&data).expect("Did not parse correctly.");
-        assert_eq!(bbox(&topology), [0_f64, 0_f64, 10_f64, 10_f64]);
+        assert_eq!(bbox(&topology), Some([0_f64, 0_f64, 10_f64, 10_f64]));
     }
 
     #[test]
@@ -161,7 +235,7 @@ mod bbox_tests {
 
         let topology: Topology =
             serde_json::from_str(&data).expect("Did not parse correctly.");
-        assert_eq!(bbox(&topology), [0_f64, 0_f64, 10_f64, 10_f64]);
+        assert_eq!(bbox(&topology), Some([0_f64, 0_f64, 10_f64, 10_f64]));
     }
 
     #[test]
@@ -179,6 +253,89 @@ mod bbox_tests {
 
         let topology: Topology =
             serde_json::from_str(&data).expect("Did not parse correctly.");
-        assert_eq!(bbox(&topology), [0_f64, 0_f64, 10_f64, 10_f64]);
+        assert_eq!(bbox(&topology), Some([0_f64, 0_f64, 10_f64, 10_f64]));
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn object_bbox_only_considers_referenced_arcs() {
+        let topology = Topology {
+            arcs: vec![
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                    vec![1_f64, 1_f64],
+                    vec![0_f64, 0_f64],
+                ],
+                vec![vec![2_f64, 0_f64], vec![3_f64, 2_f64]],
+            ],
+            objects: vec![
+                NamedGeometry {
+                    name: "triangle".to_string(),
+                    geometry: topojson::Geometry::new(Value::Polygon(vec![
+                        vec![0],
+                    ])),
+                },
+                NamedGeometry {
+                    name: "collection".to_string(),
+                    geometry: topojson::Geometry::new(
+                        Value::GeometryCollection(vec![
+                            topojson::Geometry::new(Value::LineString(vec![
+                                -2,
+                            ])),
+                            topojson::Geometry::new(Value::Point(vec![
+                                5_f64, 5_f64,
+                            ])),
+                        ]),
+                    ),
+                },
+                NamedGeometry {
+                    name: "empty".to_string(),
+                    geometry: topojson::Geometry::new(
+                        Value::GeometryCollection(vec![]),
+                    ),
+                },
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            object_bbox(&topology, "triangle"),
+            Some([0_f64, 0_f64, 1_f64, 1_f64])
+        );
+        assert_eq!(
+            object_bbox(&topology, "collection"),
+            Some([2_f64, 0_f64, 5_f64, 5_f64])
+        );
+        assert_eq!(object_bbox(&topology, "empty"), None);
+        assert_eq!(object_bbox(&topology, "missing"), None);
+        assert_eq!(
+            geometry_bbox(
+                &topology,
+                &topojson::Geometry::new(Value::MultiPolygon(vec![vec![
+                    vec![0],
+                ]]))
+            ),
+            Some([0_f64, 0_f64, 1_f64, 1_f64])
+        );
+        assert_eq!(bbox(&topology), Some([0_f64, 0_f64, 5_f64, 5_f64]));
+    }
+
+    #[test]
+    fn object_bbox_decodes_quantized_arcs() {
+        let mut file = File::open("./tests/topojson/polygon-q1e4.json")
+            .expect("Could not load json file.");
+        let mut data = String::new();
+        file.read_to_string(&mut data)
+            .expect("Did not read file correctly.");
+
+        let topology: Topology =
+            serde_json::from_str(&data).expect("Did not parse correctly.");
+        assert_eq!(
+            object_bbox(&topology, "polygon"),
+            Some([0_f64, 0_f64, 10_f64, 10_f64])
+        );
     }
 }
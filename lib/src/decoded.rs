@@ -0,0 +1,198 @@
+use std::ops::Deref;
+
+use topojson::{Arc, NamedGeometry, Topology, Value};
+
+use crate::transform::Transform;
+
+/// A topology whose arcs, points and multipoints have been decoded into
+/// absolute coordinates, once, up front.
+///
+/// It dereferences to an untransformed [`Topology`], so it can be passed to
+/// `feature()`, `merge()`, `mesh()`, `bbox()` and the other functions of
+/// this crate in place of the original. None of them then need to delta
+/// decode an arc, which pays off when many objects are extracted from the
+/// same topology.
+///
+/// Arc indexes are unchanged, but the positions of `Point` and `MultiPoint`
+/// geometries are decoded, so take those from the decoded objects.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedTopology(Topology);
+
+impl DecodedTopology {
+    /// Decodes the topology.
+    #[must_use]
+    pub fn new(topology: &Topology) -> Self {
+        let t = Transform::new(topology.transform.as_ref());
+
+        let arcs = topology
+            .arcs
+            .iter()
+            .map(|arc| -> Arc {
+                let mut d = t.decoder();
+                arc.iter()
+                    .map(|p| {
+                        let mut out = Vec::with_capacity(p.len());
+                        d.decode_into(p, &mut out);
+                        out
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let objects = topology
+            .objects
+            .iter()
+            .map(|o| NamedGeometry {
+                name: o.name.clone(),
+                geometry: decode_geometry(&t, &o.geometry),
+            })
+            .collect();
+
+        Self(Topology {
+            bbox: topology.bbox.clone(),
+            objects,
+            transform: None,
+            arcs,
+            foreign_members: topology.foreign_members.clone(),
+        })
+    }
+
+    /// Returns the decoded topology.
+    #[must_use]
+    pub fn into_inner(self) -> Topology {
+        self.0
+    }
+}
+
+impl From<&Topology> for DecodedTopology {
+    fn from(topology: &Topology) -> Self {
+        Self::new(topology)
+    }
+}
+
+impl Deref for DecodedTopology {
+    type Target = Topology;
+
+    fn deref(&self) -> &Topology {
+        &self.0
+    }
+}
+
+impl AsRef<Topology> for DecodedTopology {
+    fn as_ref(&self) -> &Topology {
+        &self.0
+    }
+}
+
+/// Decodes the positions of `Point` and `MultiPoint` geometries.
+pub(crate) fn decode_geometry(
+    t: &Transform,
+    input: &topojson::Geometry,
+) -> topojson::Geometry {
+    let value = match &input.value {
+        Value::GeometryCollection(gc) => Value::GeometryCollection(
+            gc.iter().map(|g| decode_geometry(t, g)).collect(),
+        ),
+        Value::Point(p) => Value::Point(point(t, p)),
+        Value::MultiPoint(mp) => {
+            Value::MultiPoint(mp.iter().map(|p| point(t, p)).collect())
+        }
+        _ => return input.clone(),
+    };
+
+    topojson::Geometry {
+        bbox: input.bbox.clone(),
+        value,
+        properties: input.properties.clone(),
+        id: input.id.clone(),
+        foreign_members: input.foreign_members.clone(),
+    }
+}
+
+/// Transform a single point, keeping any extra dimensions.
+fn point(t: &Transform, p: &[f64]) -> Vec<f64> {
+    let mut out = Vec::with_capacity(p.len());
+    t.decoder().decode_into(p, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod decoded_tests {
+    use std::fs::File;
+
+    use geo::Geometry;
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::bbox::bbox;
+    use crate::feature::feature;
+    use crate::merge::merge;
+    use crate::mesh::mesh;
+
+    fn read(path: &str) -> Topology {
+        let file = File::open(path).expect("Could not load json file.");
+        serde_json::from_reader(file).expect("Did not parse correctly.")
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn decodes_arcs_and_points() {
+        let topology = read("./tests/topojson/points-q1e5.json");
+        let decoded = DecodedTopology::new(&topology);
+
+        assert_eq!(decoded.transform, None);
+        assert_eq!(decoded.objects.len(), topology.objects.len());
+        for o in &topology.objects {
+            let d = &decoded
+                .objects
+                .iter()
+                .find(|d| d.name == o.name)
+                .expect("object should be present")
+                .geometry;
+            assert_eq!(
+                feature::<f64>(&decoded, &d.value),
+                feature::<f64>(&topology, &o.geometry.value)
+            );
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn is_accepted_in_place_of_the_topology() {
+        let topology = read("./tests/topojson/polygon-q1e4.json");
+        let decoded = DecodedTopology::from(&topology);
+        assert_eq!(
+            decoded.arcs[0],
+            vec![
+                vec![0_f64, 0_f64],
+                vec![0_f64, 9999_f64 * 0.001_000_100_010_001_f64],
+                vec![
+                    9999_f64 * 0.001_000_100_010_001_f64,
+                    9999_f64 * 0.001_000_100_010_001_f64
+                ],
+                vec![9999_f64 * 0.001_000_100_010_001_f64, 0_f64],
+                vec![0_f64, 0_f64],
+            ]
+        );
+
+        let object = &topology.objects[0].geometry;
+        let decoded_object = &decoded.objects[0].geometry;
+        assert_eq!(
+            feature::<f64>(&decoded, &decoded_object.value),
+            feature::<f64>(&topology, &object.value)
+        );
+        assert_eq!(
+            merge::<f64>(&decoded, &decoded.objects),
+            merge::<f64>(&topology, &topology.objects)
+        );
+        assert_eq!(
+            mesh::<f64>(&decoded, Some(decoded_object), None),
+            mesh::<f64>(&topology, Some(object), None)
+        );
+        assert_eq!(bbox(&decoded), bbox(&topology));
+        assert!(matches!(
+            feature::<f64>(&decoded, &decoded_object.value),
+            Geometry::Polygon(_)
+        ));
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use geo::CoordFloat;
+use geo::Geometry;
+use serde_json::Value as JsonValue;
+use topojson::{NamedGeometry, Topology, Value};
+
+use crate::feature::feature;
+use crate::merge::merge_arcs;
+
+/// Returns a key function, for use with [`dissolve`], which groups members
+/// by the value of a property.
+///
+/// Strings are used as they are, other values as formatted by `serde_json`.
+/// Members without the property are left out.
+pub fn property_key(
+    name: &str,
+) -> impl Fn(&topojson::Geometry) -> Option<String> + '_ {
+    move |g| match g.properties.as_ref()?.get(name)? {
+        JsonValue::Null => None,
+        JsonValue::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Groups the members of the named object by key, and merges each group.
+///
+/// The key function is given each member of the object, a geometry
+/// collection, and members for which it returns `None` are left out. An
+/// object which is not a collection is a group of one. The groups are
+/// returned in the order their keys are first seen.
+///
+/// Returns `None` when the topology does not contain the object.
+#[must_use]
+pub fn dissolve<T, K, F>(
+    topology: &Topology,
+    object_name: &str,
+    key: F,
+) -> Option<Vec<(K, Geometry<T>)>>
+where
+    T: CoordFloat + Debug,
+    K: Ord + Clone,
+    F: FnMut(&topojson::Geometry) -> Option<K>,
+{
+    let groups = dissolve_arcs(topology, object_name, key)?;
+    Some(
+        groups
+            .into_iter()
+            .map(|(k, g)| (k, feature(topology, &g.value)))
+            .collect(),
+    )
+}
+
+/// Groups the members of the named object by key, and merges each group
+/// into a `TopoJSON` `MultiPolygon`.
+///
+/// The arc indexes refer to the arcs of the topology, see [`dissolve`].
+#[must_use]
+pub fn dissolve_arcs<K, F>(
+    topology: &Topology,
+    object_name: &str,
+    mut key: F,
+) -> Option<Vec<(K, topojson::Geometry)>>
+where
+    K: Ord + Clone,
+    F: FnMut(&topojson::Geometry) -> Option<K>,
+{
+    let object = topology.objects.iter().find(|o| o.name == object_name)?;
+    let members = match &object.geometry.value {
+        Value::GeometryCollection(gc) => gc.as_slice(),
+        _ => std::slice::from_ref(&object.geometry),
+    };
+
+    let mut index: BTreeMap<K, usize> = BTreeMap::new();
+    let mut groups: Vec<(K, Vec<topojson::Geometry>)> = vec![];
+    for member in members {
+        let Some(k) = key(member) else {
+            continue;
+        };
+        if let Some(&i) = index.get(&k) {
+            groups[i].1.push(member.clone());
+        } else {
+            index.insert(k.clone(), groups.len());
+            groups.push((k, vec![member.clone()]));
+        }
+    }
+
+    Some(
+        groups
+            .into_iter()
+            .map(|(k, members)| {
+                let group = NamedGeometry {
+                    name: object_name.to_string(),
+                    geometry: topojson::Geometry::new(
+                        Value::GeometryCollection(members),
+                    ),
+                };
+                (k, merge_arcs(topology, &[group]))
+            })
+            .collect(),
+    )
+}
+
+/// Groups the members of the named object by key, and returns a new
+/// object holding one `MultiPolygon` per group, with the key as its id.
+///
+/// The geometries reference the existing arcs, so the result can be pushed
+/// onto the objects of the topology, as topomerge's `-k` mode does.
+#[must_use]
+pub fn dissolve_object<K, F>(
+    topology: &Topology,
+    object_name: &str,
+    target_name: &str,
+    key: F,
+) -> Option<NamedGeometry>
+where
+    K: Ord + Clone + Into<JsonValue>,
+    F: FnMut(&topojson::Geometry) -> Option<K>,
+{
+    let geometries = dissolve_arcs(topology, object_name, key)?
+        .into_iter()
+        .map(|(k, mut g)| {
+            g.id = Some(k.into());
+            g
+        })
+        .collect();
+
+    Some(NamedGeometry {
+        name: target_name.to_string(),
+        geometry: topojson::Geometry::new(Value::GeometryCollection(
+            geometries,
+        )),
+    })
+}
+
+#[cfg(test)]
+mod dissolve_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::merge::merge;
+
+    fn county(
+        id: &str,
+        state: Option<JsonValue>,
+        value: Value,
+    ) -> topojson::Geometry {
+        let mut g = topojson::Geometry::new(value);
+        g.id = Some(json!(id));
+        if let Some(state) = state {
+            g.properties = Some(
+                json!({ "state": state })
+                    .as_object()
+                    .expect("an object")
+                    .clone(),
+            );
+        }
+        g
+    }
+
+    ///
+    /// +----+----+  +----+
+    /// | a  | b  |  | c  |
+    /// +----+----+  +----+
+    ///
+    fn topology() -> Topology {
+        Topology {
+            arcs: vec![
+                vec![vec![1_f64, 1_f64], vec![1_f64, 0_f64]],
+                vec![
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![2_f64, 1_f64],
+                    vec![2_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                ],
+                vec![
+                    vec![3_f64, 0_f64],
+                    vec![3_f64, 1_f64],
+                    vec![4_f64, 1_f64],
+                    vec![4_f64, 0_f64],
+                    vec![3_f64, 0_f64],
+                ],
+            ],
+            objects: vec![NamedGeometry {
+                name: "counties".to_string(),
+                geometry: topojson::Geometry::new(Value::GeometryCollection(
+                    vec![
+                        county(
+                            "a",
+                            Some(json!("X")),
+                            Value::Polygon(vec![vec![0, 1]]),
+                        ),
+                        county(
+                            "c",
+                            Some(json!(1)),
+                            Value::Polygon(vec![vec![3]]),
+                        ),
+                        county(
+                            "b",
+                            Some(json!("X")),
+                            Value::Polygon(vec![vec![-1, 2]]),
+                        ),
+                        county("d", None, Value::Polygon(vec![vec![3]])),
+                    ],
+                )),
+            }],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        }
+    }
+
+    fn members(topology: &Topology, ids: &[&str]) -> Vec<NamedGeometry> {
+        let Value::GeometryCollection(gc) = &topology.objects[0].geometry.value
+        else {
+            unreachable!("counties is a collection");
+        };
+        gc.iter()
+            .filter(|g| ids.iter().any(|id| g.id == Some(json!(id))))
+            .map(|g| NamedGeometry {
+                name: "counties".to_string(),
+                geometry: g.clone(),
+            })
+            .collect()
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn groups_members_by_property() {
+        let topology = topology();
+        let states =
+            dissolve::<f64, _, _>(&topology, "counties", property_key("state"))
+                .expect("counties is present");
+
+        assert_eq!(
+            states,
+            vec![
+                (
+                    "X".to_string(),
+                    merge(&topology, &members(&topology, &["a", "b"]))
+                ),
+                (
+                    "1".to_string(),
+                    merge(&topology, &members(&topology, &["c"]))
+                ),
+            ]
+        );
+        assert_eq!(
+            dissolve::<f64, String, _>(&topology, "missing", |_| None),
+            None
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn groups_members_by_closure() {
+        let topology = topology();
+        let groups = dissolve_arcs(&topology, "counties", |g| {
+            g.id.as_ref().map(|id| id != &json!("c"))
+        })
+        .expect("counties is present");
+
+        assert_eq!(
+            groups,
+            vec![
+                (
+                    true,
+                    merge_arcs(
+                        &topology,
+                        &members(&topology, &["a", "b", "d"])
+                    )
+                ),
+                (false, merge_arcs(&topology, &members(&topology, &["c"]))),
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn writes_the_groups_back_as_an_object() {
+        let mut topology = topology();
+        let states = dissolve_object(
+            &topology,
+            "counties",
+            "states",
+            property_key("state"),
+        )
+        .expect("counties is present");
+        topology.objects.push(states);
+
+        let Value::GeometryCollection(gc) = &topology.objects[1].geometry.value
+        else {
+            unreachable!("states is a collection");
+        };
+        assert_eq!(
+            gc.iter().map(|g| g.id.clone()).collect::<Vec<_>>(),
+            vec![Some(json!("X")), Some(json!("1"))]
+        );
+        assert_eq!(gc[0].value, Value::MultiPolygon(vec![vec![vec![1, 2]]]));
+        assert_eq!(gc[1].value, Value::MultiPolygon(vec![vec![vec![3]]]));
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt;
+
+use topojson::{ArcIndexes, Topology, TransformParams, Value};
+
+use crate::load::{ArcTable, FlatTopology};
+use crate::quantize::QuantizeError;
+use crate::translate;
+
+/// Reasons why a topology cannot be processed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// An arc index refers to an arc which does not exist.
+    ArcIndexOutOfRange {
+        /// The arc index, as found in the geometry.
+        index: i32,
+        /// The number of arcs in the topology.
+        len: usize,
+    },
+    /// An arc has too few positions.
+    EmptyArc {
+        /// The index of the arc in the topology.
+        arc: usize,
+    },
+    /// A line or a ring does not reference any arcs.
+    EmptyArcIndexes,
+    /// A position has fewer than two coordinates.
+    InvalidPosition,
+    /// The transform scale is zero, or the transform is not finite.
+    InvalidTransform,
+    /// The operation does not accept this type of geometry.
+    UnexpectedGeometry {
+        /// The types of geometry which are accepted.
+        expected: &'static str,
+        /// The type of geometry which was found.
+        found: &'static str,
+    },
+    /// The topology cannot be quantized.
+    Quantize(QuantizeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ArcIndexOutOfRange { index, len } => write!(
+                f,
+                "arc index {index} is out of range, the topology has {len} arcs"
+            ),
+            Self::EmptyArc { arc } => {
+                write!(f, "arc {arc} has too few positions")
+            }
+            Self::EmptyArcIndexes => {
+                write!(f, "a line or ring does not reference any arcs")
+            }
+            Self::InvalidPosition => {
+                write!(f, "a position has fewer than two coordinates")
+            }
+            Self::InvalidTransform => write!(f, "invalid transform"),
+            Self::UnexpectedGeometry { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            Self::Quantize(e) => write!(f, "cannot quantize: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Quantize(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<QuantizeError> for Error {
+    fn from(e: QuantizeError) -> Self {
+        Self::Quantize(e)
+    }
+}
+
+/// Returns the `TopoJSON` type name of the geometry.
+pub const fn type_name(o: &Value) -> &'static str {
+    match o {
+        Value::Point(_) => "Point",
+        Value::MultiPoint(_) => "MultiPoint",
+        Value::LineString(_) => "LineString",
+        Value::MultiLineString(_) => "MultiLineString",
+        Value::Polygon(_) => "Polygon",
+        Value::MultiPolygon(_) => "MultiPolygon",
+        Value::GeometryCollection(_) => "GeometryCollection",
+    }
+}
+
+/// Checks the parts of a topology which an operation depends upon, so that
+/// the operation itself cannot panic.
+#[derive(Debug)]
+pub struct Check<'a> {
+    arcs: ArcTable<'a>,
+    // Arcs shared by several geometries are only checked once.
+    checked: Vec<bool>,
+    // The minimum number of positions in an arc.
+    min_arc_len: usize,
+}
+
+impl<'a> Check<'a> {
+    /// Checks the transform of the topology.
+    ///
+    /// `feature()` accepts single position arcs, `stitch()` needs at least
+    /// two.
+    pub fn new(
+        topology: &'a Topology,
+        min_arc_len: usize,
+    ) -> Result<Self, Error> {
+        Self::with_arcs(
+            topology.transform.as_ref(),
+            ArcTable::Nested(&topology.arcs),
+            min_arc_len,
+        )
+    }
+
+    /// Checks the transform of a topology read by `load::load()`.
+    pub fn new_flat(
+        topology: &'a FlatTopology,
+        min_arc_len: usize,
+    ) -> Result<Self, Error> {
+        Self::with_arcs(
+            topology.transform.as_ref(),
+            ArcTable::Flat(&topology.arcs),
+            min_arc_len,
+        )
+    }
+
+    fn with_arcs(
+        transform: Option<&TransformParams>,
+        arcs: ArcTable<'a>,
+        min_arc_len: usize,
+    ) -> Result<Self, Error> {
+        if let Some(tp) = transform {
+            let finite =
+                tp.scale.iter().chain(&tp.translate).all(|x| x.is_finite());
+            if !finite || tp.scale.contains(&0_f64) {
+                return Err(Error::InvalidTransform);
+            }
+        }
+
+        Ok(Self {
+            arcs,
+            checked: vec![false; arcs.len()],
+            min_arc_len,
+        })
+    }
+
+    pub const fn position(p: &[f64]) -> Result<(), Error> {
+        if p.len() < 2 {
+            return Err(Error::InvalidPosition);
+        }
+        Ok(())
+    }
+
+    pub fn arc(&mut self, i: i32) -> Result<(), Error> {
+        let index = translate(i);
+        let Some(len) = self.arcs.arc_len(index) else {
+            return Err(Error::ArcIndexOutOfRange {
+                index: i,
+                len: self.arcs.len(),
+            });
+        };
+        if self.checked[index] {
+            return Ok(());
+        }
+        if len < self.min_arc_len.max(1) {
+            return Err(Error::EmptyArc { arc: index });
+        }
+        if !self.arcs.arc_has_valid_positions(index) {
+            return Err(Error::InvalidPosition);
+        }
+        self.checked[index] = true;
+        Ok(())
+    }
+
+    /// Checks every arc in the topology.
+    pub fn arcs(&mut self) -> Result<(), Error> {
+        for i in 0..self.arcs.len() {
+            let i =
+                i32::try_from(i).map_err(|_| Error::ArcIndexOutOfRange {
+                    index: i32::MAX,
+                    len: self.arcs.len(),
+                })?;
+            self.arc(i)?;
+        }
+        Ok(())
+    }
+
+    pub fn line(&mut self, arcs: &[i32]) -> Result<(), Error> {
+        if arcs.is_empty() {
+            return Err(Error::EmptyArcIndexes);
+        }
+        for a in arcs {
+            self.arc(*a)?;
+        }
+        Ok(())
+    }
+
+    pub fn polygon(&mut self, rings: &[ArcIndexes]) -> Result<(), Error> {
+        for ring in rings {
+            self.line(ring)?;
+        }
+        Ok(())
+    }
+
+    /// For collections recursively check objects.
+    pub fn geometry(&mut self, o: &Value) -> Result<(), Error> {
+        match o {
+            Value::GeometryCollection(gc) => {
+                for g in gc {
+                    self.geometry(&g.value)?;
+                }
+            }
+            Value::Point(p) => Self::position(p)?,
+            Value::MultiPoint(mp) => {
+                for p in mp {
+                    Self::position(p)?;
+                }
+            }
+            Value::LineString(arcs) => self.line(arcs)?,
+            Value::MultiLineString(arcs) | Value::Polygon(arcs) => {
+                self.polygon(arcs)?;
+            }
+            Value::MultiPolygon(mp) => {
+                for p in mp {
+                    self.polygon(p)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
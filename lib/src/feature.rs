@@ -1,3 +1,5 @@
+use std::marker::PhantomData;
+
 use geo::Coord;
 use geo::CoordFloat;
 use geo::Geometry;
@@ -9,14 +11,22 @@ use geo::MultiPolygon;
 use geo::Point;
 use geo::Polygon;
 use geo::line_string;
-use topojson::Arc;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::Map;
+use serde_json::Value as JsonValue;
 use topojson::ArcIndexes;
+use topojson::Bbox;
 use topojson::Topology;
 use topojson::Value;
 
+use crate::error::Check;
+use crate::error::Error;
+use crate::load::ArcTable;
+use crate::load::FlatTopology;
 use crate::reverse::reverse;
 use crate::transform::Transform;
-use crate::transform::gen_transform;
+use crate::translate;
 
 /// Given a object name find convert and return a Geometry object.
 ///
@@ -56,54 +66,435 @@ where
     //     Some(transform_params) => transform_params,
     // };
 
-    Builder {
-        arcs: topology.arcs.clone(),
-        transform: gen_transform(&topology.transform),
+    Builder::new(topology).geometry(o)
+}
+
+/// Given a json gemetry value apply a transform and convert.
+///
+/// The version of [`feature`] for a topology read by `load::load()`.
+#[must_use]
+pub fn feature_flat<T>(topology: &FlatTopology, o: &Value) -> Geometry<T>
+where
+    T: CoordFloat,
+{
+    Builder::new_flat(topology).geometry(o)
+}
+
+/// Given a json gemetry value apply a transform and convert.
+///
+/// The fallible version of [`feature_flat`].
+///
+/// # Errors
+///
+/// When the geometry references arcs which do not exist, when an arc, line
+/// or position is empty, or when the transform is malformed.
+pub fn try_feature_flat<T>(
+    topology: &FlatTopology,
+    o: &Value,
+) -> Result<Geometry<T>, Error>
+where
+    T: CoordFloat,
+{
+    Check::new_flat(topology, 1)?.geometry(o)?;
+    Ok(feature_flat(topology, o))
+}
+
+/// Given a json gemetry value apply a transform and convert.
+///
+/// The fallible version of [`feature`].
+///
+/// # Errors
+///
+/// When the geometry references arcs which do not exist, when an arc, line
+/// or position is empty, or when the transform is malformed.
+pub fn try_feature<T>(
+    topology: &Topology,
+    o: &Value,
+) -> Result<Geometry<T>, Error>
+where
+    T: CoordFloat,
+{
+    Check::new(topology, 1)?.geometry(o)?;
+    Ok(feature(topology, o))
+}
+
+/// A decoded geometry, together with the dimensions beyond x and y of each
+/// of its vertices, such as an elevation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeometryZ<T>
+where
+    T: CoordFloat,
+{
+    /// The decoded geometry.
+    pub geometry: Geometry<T>,
+    /// The extra dimensions of each vertex, in the order of
+    /// `geo::CoordsIter::coords_iter()`, so including the closing vertex of
+    /// each ring. Empty for a vertex which has none.
+    pub z: Vec<Vec<f64>>,
+}
+
+impl<T> GeometryZ<T>
+where
+    T: CoordFloat,
+{
+    /// Returns the third dimension of each vertex, `None` when it has only
+    /// two.
+    pub fn elevations(&self) -> impl Iterator<Item = Option<f64>> + '_ {
+        self.z.iter().map(|z| z.first().copied())
+    }
+}
+
+/// Given a object name find and convert it, keeping the extra dimensions.
+///
+/// None: -
+///   * The object subsection does not contain the name.
+#[must_use]
+pub fn feature_z_from_name<T>(
+    topology: &Topology,
+    name: &str,
+) -> Option<GeometryZ<T>>
+where
+    T: CoordFloat,
+{
+    topology
+        .objects
+        .iter()
+        .find(|x| x.name == name)
+        .map(|ng| feature_z(topology, &ng.geometry.value))
+}
+
+/// Given a json gemetry value apply a transform and convert.
+///
+/// The version of [`feature`] which also returns the dimensions beyond x
+/// and y, which `geo` geometries cannot hold. These are neither scaled nor
+/// translated by the transform.
+#[must_use]
+pub fn feature_z<T>(topology: &Topology, o: &Value) -> GeometryZ<T>
+where
+    T: CoordFloat,
+{
+    let builder = Builder::new(topology);
+    let geometry = builder.geometry(o);
+    let mut z = vec![];
+    builder.extra(o, &geometry, &mut z);
+    GeometryZ { geometry, z }
+}
+
+/// Given a json gemetry value apply a transform and convert.
+///
+/// The parallel version of [`feature`], the members of geometry collections
+/// and the polygons of a `MultiPolygon` are converted on the rayon thread
+/// pool.
+#[cfg(feature = "rayon")]
+#[must_use]
+pub fn feature_par<T>(topology: &Topology, o: &Value) -> Geometry<T>
+where
+    T: CoordFloat + Send,
+{
+    Builder::new(topology).geometry_par(o)
+}
+
+/// A decoded geometry, together with the id and properties of the
+/// `TopoJSON` object it was extracted from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feature<T>
+where
+    T: CoordFloat,
+{
+    /// The bounding box of the object, if any.
+    pub bbox: Option<Bbox>,
+    /// The decoded geometry.
+    pub geometry: Geometry<T>,
+    /// The id of the object, if any.
+    pub id: Option<JsonValue>,
+    /// The properties of the object, empty when the object has none.
+    pub properties: Map<String, JsonValue>,
+    /// Any other members of the object.
+    pub foreign_members: Option<Map<String, JsonValue>>,
+}
+
+/// A list of features.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeatureCollection<T>
+where
+    T: CoordFloat,
+{
+    /// The features, one per member of the geometry collection.
+    pub features: Vec<Feature<T>>,
+}
+
+/// The output of `feature_object()`.
+///
+/// A top-level geometry collection becomes a feature collection, any other
+/// object becomes a single feature.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeatureObject<T>
+where
+    T: CoordFloat,
+{
+    /// A single feature.
+    Feature(Feature<T>),
+    /// A list of features.
+    FeatureCollection(FeatureCollection<T>),
+}
+
+/// Given a object name find and convert the object into a feature or a
+/// feature collection.
+///
+/// None: -
+///   * The object subsection does not contain the name.
+#[inline]
+#[must_use]
+pub fn feature_object_from_name<T>(
+    topology: &Topology,
+    name: &str,
+) -> Option<FeatureObject<T>>
+where
+    T: CoordFloat,
+{
+    topology
+        .objects
+        .iter()
+        .find(|x| x.name == name)
+        .map(|ng| feature_object(topology, &ng.geometry))
+}
+
+/// Convert a object into a feature, keeping the id, bbox, properties and
+/// foreign members of the object.
+///
+/// As in topojson-client a top-level geometry collection is mapped to a
+/// feature collection. The collection's own id and properties are dropped,
+/// but those of second-level geometry collections are kept.
+#[must_use]
+pub fn feature_object<T>(
+    topology: &Topology,
+    o: &topojson::Geometry,
+) -> FeatureObject<T>
+where
+    T: CoordFloat,
+{
+    let builder = Builder::new(topology);
+
+    match &o.value {
+        Value::GeometryCollection(gc) => {
+            FeatureObject::FeatureCollection(FeatureCollection {
+                features: gc.iter().map(|g| builder.feature(g)).collect(),
+            })
+        }
+        _ => FeatureObject::Feature(builder.feature(o)),
+    }
+}
+
+/// Given a object name, return an iterator which decodes the object one
+/// feature at a time.
+///
+/// The members of a geometry collection are yielded in order, any other
+/// object yields a single feature. Each feature keeps the id and properties
+/// of its member, see [`feature_object`].
+///
+/// None: -
+///   * The object subsection does not contain the name.
+#[must_use]
+pub fn features_iter<'a, T>(
+    topology: &'a Topology,
+    name: &str,
+) -> Option<FeaturesIter<'a, T>>
+where
+    T: CoordFloat,
+{
+    let ng = topology.objects.iter().find(|x| x.name == name)?;
+    let geometries = match &ng.geometry.value {
+        Value::GeometryCollection(gc) => gc.iter(),
+        _ => std::slice::from_ref(&ng.geometry).iter(),
+    };
+
+    Some(FeaturesIter {
+        builder: Builder::new(topology),
+        geometries,
+        phantom: PhantomData,
+    })
+}
+
+/// The iterator returned by [`features_iter`].
+///
+/// Nothing is decoded until a feature is requested.
+#[derive(Debug)]
+pub struct FeaturesIter<'a, T> {
+    builder: Builder<'a>,
+    geometries: std::slice::Iter<'a, topojson::Geometry>,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Iterator for FeaturesIter<'_, T>
+where
+    T: CoordFloat,
+{
+    type Item = Feature<T>;
+
+    fn next(&mut self) -> Option<Feature<T>> {
+        self.geometries.next().map(|g| self.builder.feature(g))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.geometries.size_hint()
     }
-    .geometry(o)
 }
 
+impl<T> ExactSizeIterator for FeaturesIter<'_, T> where T: CoordFloat {}
+
 /// State holds data extracted from a Topological object.
-struct Builder {
-    arcs: Vec<Arc>,
+#[derive(Debug)]
+struct Builder<'a> {
+    /// Borrowed, so that decoding an object does not copy the arc table.
+    arcs: ArcTable<'a>,
 
     transform: Transform,
 }
 
-impl Builder {
+impl<'a> Builder<'a> {
+    fn new(topology: &'a Topology) -> Self {
+        Self {
+            arcs: ArcTable::Nested(&topology.arcs),
+            transform: Transform::new(topology.transform.as_ref()),
+        }
+    }
+
+    const fn new_flat(topology: &'a FlatTopology) -> Self {
+        Self {
+            arcs: ArcTable::Flat(&topology.arcs),
+            transform: Transform::new(topology.transform.as_ref()),
+        }
+    }
+
     /// Convert the index found in a Geometry object into a point.
     ///
     /// Using the top level arcs array as reference.
-    fn arc(&mut self, i: i32, points: &mut Vec<(f64, f64)>) {
+    fn arc(&self, i: i32, points: &mut Vec<(f64, f64)>) {
         if !points.is_empty() {
             points.pop();
         }
 
         // As per spec. negative indicies are bit wise NOT converted.
         let index = if i < 0 { !i } else { i } as usize;
-        let a = &self.arcs[index];
-        let n = a.len();
-        for (k, v) in a.iter().enumerate() {
-            let t = (self.transform)(v, k);
-            points.push((t[0], t[1]));
-        }
+        let n = self.arcs.decode(index, &self.transform, points);
 
         if i < 0 {
             reverse(points, n);
         }
     }
 
+    /// Append the extra dimensions of the positions of an arc, stitched as
+    /// in `arc()`.
+    fn arc_extra(&self, i: i32, extra: &mut Vec<&'a [f64]>) {
+        if !extra.is_empty() {
+            extra.pop();
+        }
+
+        let index = translate(i);
+        let n = self.arcs.extra(index, extra);
+
+        if i < 0 {
+            reverse(extra, n);
+        }
+    }
+
+    fn line_extra(&self, arcs: &[i32]) -> Vec<&'a [f64]> {
+        let mut extra = Vec::with_capacity(arcs.len() + 1);
+        for a in arcs {
+            self.arc_extra(*a, &mut extra);
+        }
+
+        if extra.len() < 2 {
+            extra.push(extra[0]);
+        }
+
+        extra
+    }
+
+    /// Pads the extra dimensions of the ring to `len`, the number of
+    /// vertices of the decoded ring, which `ring()` and `polygon()` pad and
+    /// close by repeating the first vertex.
+    fn ring_extra(&self, arcs: &[i32], len: usize, out: &mut Vec<Vec<f64>>) {
+        let mut extra = self.line_extra(arcs);
+        let first = extra[0];
+        extra.resize(len.max(extra.len()), first);
+        out.extend(extra.into_iter().map(<[f64]>::to_vec));
+    }
+
+    fn polygon_extra<T>(
+        &self,
+        topo_polygon: &[ArcIndexes],
+        polygon: &Polygon<T>,
+        out: &mut Vec<Vec<f64>>,
+    ) where
+        T: CoordFloat,
+    {
+        let rings =
+            std::iter::once(polygon.exterior()).chain(polygon.interiors());
+        for (ring, ls) in topo_polygon.iter().zip(rings) {
+            self.ring_extra(ring, ls.0.len(), out);
+        }
+    }
+
+    /// Append the extra dimensions of each vertex of the geometry to `out`,
+    /// in the order `geometry()` emits the vertices.
+    ///
+    /// `g` is the geometry decoded from `o`, giving the length of each ring.
+    fn extra<T>(&self, o: &Value, g: &Geometry<T>, out: &mut Vec<Vec<f64>>)
+    where
+        T: CoordFloat,
+    {
+        match (o, g) {
+            (
+                Value::GeometryCollection(topo_geometries),
+                Geometry::GeometryCollection(gc),
+            ) => {
+                for (topo_g, g) in topo_geometries.iter().zip(gc) {
+                    self.extra(&topo_g.value, g, out);
+                }
+            }
+            (Value::Point(p), _) => out.push(p[2..].to_vec()),
+            (Value::MultiPoint(mp), _) => {
+                out.extend(mp.iter().map(|p| p[2..].to_vec()));
+            }
+            (Value::LineString(topo_ls), _) => {
+                out.extend(
+                    self.line_extra(topo_ls).into_iter().map(<[f64]>::to_vec),
+                );
+            }
+            (Value::MultiLineString(topo_mls), _) => {
+                for topo_ls in topo_mls {
+                    out.extend(
+                        self.line_extra(topo_ls)
+                            .into_iter()
+                            .map(<[f64]>::to_vec),
+                    );
+                }
+            }
+            (Value::Polygon(topo_polygon), Geometry::Polygon(polygon)) => {
+                self.polygon_extra(topo_polygon, polygon, out);
+            }
+            (Value::MultiPolygon(topo_mp), Geometry::MultiPolygon(mp)) => {
+                for (topo_polygon, polygon) in topo_mp.iter().zip(mp) {
+                    self.polygon_extra(topo_polygon, polygon, out);
+                }
+            }
+            // `geometry()` decodes each value into the matching type.
+            _ => {}
+        }
+    }
+
     /// Transform a single point.
     #[inline]
-    fn point(&mut self, p: &[f64]) -> Vec<f64> {
-        (self.transform)(p, 0)
+    fn point(&self, p: &[f64]) -> [f64; 2] {
+        self.transform.point(p)
     }
 
     /// Convert a array of indicies found in a Geometry object into a arrays of
     /// points.
     ///
     /// Using the top level arcs array as reference.
-    fn line(&mut self, arcs: &[i32]) -> Vec<(f64, f64)> {
+    fn line(&self, arcs: &[i32]) -> Vec<(f64, f64)> {
         let mut points: Vec<(f64, f64)> = Vec::with_capacity(arcs.len() + 1);
         for a in arcs {
             self.arc(*a, &mut points);
@@ -117,7 +508,7 @@ impl Builder {
         points
     }
 
-    fn ring(&mut self, arcs: &[i32]) -> Vec<(f64, f64)> {
+    fn ring(&self, arcs: &[i32]) -> Vec<(f64, f64)> {
         let mut points = self.line(arcs);
         // This may happen if an arc has only two points.
         while points.len() < 4 {
@@ -127,10 +518,10 @@ impl Builder {
     }
 
     #[inline]
-    fn polygon<'a>(
-        &'a mut self,
-        arcs: &'a [ArcIndexes],
-    ) -> impl Iterator<Item = LineString<f64>> + 'a {
+    fn polygon<'b>(
+        &'b self,
+        arcs: &'b [ArcIndexes],
+    ) -> impl Iterator<Item = LineString<f64>> + 'b {
         arcs.iter().map(move |x| self.ring(x)).map(|x| {
             let x1: Vec<(f64, f64)> = (*x).to_vec();
             let mut tmp: LineString<f64> = x1.into();
@@ -139,9 +530,65 @@ impl Builder {
         })
     }
 
+    fn feature<T>(&self, o: &topojson::Geometry) -> Feature<T>
+    where
+        T: CoordFloat,
+    {
+        Feature {
+            bbox: o.bbox.clone(),
+            geometry: self.geometry(&o.value),
+            id: o.id.clone(),
+            properties: o.properties.clone().unwrap_or_default(),
+            foreign_members: o.foreign_members.clone(),
+        }
+    }
+
+    /// For collections recursively build objects, in parallel.
+    #[cfg(feature = "rayon")]
+    fn geometry_par<T>(&self, o: &Value) -> Geometry<T>
+    where
+        T: CoordFloat + Send,
+    {
+        match &o {
+            Value::GeometryCollection(topo_geometries) => {
+                let geometries: Vec<Geometry<T>> = topo_geometries
+                    .par_iter()
+                    .map(|x| self.geometry_par(&x.value))
+                    .collect();
+                Geometry::GeometryCollection(GeometryCollection(geometries))
+            }
+            Value::MultiPolygon(topo_mp) => {
+                let polygon: Vec<Polygon<T>> =
+                    topo_mp.par_iter().map(|p| self.geo_polygon(p)).collect();
+                Geometry::MultiPolygon(MultiPolygon(polygon))
+            }
+            _ => self.geometry(o),
+        }
+    }
+
+    /// Converts the rings of a polygon, the first being the exterior.
+    fn geo_polygon<T>(&self, topo_polygon: &[ArcIndexes]) -> Polygon<T>
+    where
+        T: CoordFloat,
+    {
+        let mut linestring_iter = self.polygon(topo_polygon).map(|ls| {
+            ls.0.iter()
+                .map(|p| Coord {
+                    x: T::from(p.x).unwrap(),
+                    y: T::from(p.y).unwrap(),
+                })
+                .collect()
+        });
+        let exterior = linestring_iter.next();
+        exterior.map_or_else(
+            || Polygon::new(line_string![], vec![]),
+            |exterior| Polygon::new(exterior, linestring_iter.collect()),
+        )
+    }
+
     /// For collections recursively build objects.
     #[inline]
-    fn geometry<T>(&mut self, o: &Value) -> Geometry<T>
+    fn geometry<T>(&self, o: &Value) -> Geometry<T>
     where
         T: CoordFloat,
     {
@@ -202,65 +649,11 @@ impl Builder {
                 Geometry::MultiLineString(MultiLineString(mls))
             }
             Value::Polygon(topo_polygon) => {
-                let mut linestring_iter = self.polygon(topo_polygon);
-                match linestring_iter.next() {
-                    Some(exterior) => {
-                        let interior = linestring_iter
-                            .map(|ls| {
-                                ls.0.iter()
-                                    .map(|p| Coord {
-                                        x: T::from(p.x).unwrap(),
-                                        y: T::from(p.y).unwrap(),
-                                    })
-                                    .collect()
-                            })
-                            .collect();
-                        let exterior = exterior
-                            .0
-                            .iter()
-                            .map(|p| Coord {
-                                x: T::from(p.x).unwrap(),
-                                y: T::from(p.y).unwrap(),
-                            })
-                            .collect();
-                        Geometry::Polygon(Polygon::new(exterior, interior))
-                    }
-                    None => {
-                        Geometry::Polygon(Polygon::new(line_string![], vec![]))
-                    }
-                }
+                Geometry::Polygon(self.geo_polygon(topo_polygon))
             }
             Value::MultiPolygon(topo_mp) => {
-                let polygon: Vec<Polygon<T>> = topo_mp
-                    .iter()
-                    .map(|topo_polygon| {
-                        let mut linestring_iter = self.polygon(topo_polygon);
-                        match linestring_iter.next() {
-                            Some(exterior) => {
-                                let exterior = exterior
-                                    .0
-                                    .iter()
-                                    .map(|p| Coord {
-                                        x: T::from(p.x).unwrap(),
-                                        y: T::from(p.y).unwrap(),
-                                    })
-                                    .collect();
-                                let interior = linestring_iter
-                                    .map(|ls| {
-                                        ls.0.iter()
-                                            .map(|p| Coord {
-                                                x: T::from(p.x).unwrap(),
-                                                y: T::from(p.y).unwrap(),
-                                            })
-                                            .collect()
-                                    })
-                                    .collect();
-                                Polygon::new(exterior, interior)
-                            }
-                            None => Polygon::new(line_string![], vec![]),
-                        }
-                    })
-                    .collect();
+                let polygon: Vec<Polygon<T>> =
+                    topo_mp.iter().map(|p| self.geo_polygon(p)).collect();
 
                 Geometry::MultiPolygon(MultiPolygon(polygon))
             }
@@ -273,6 +666,7 @@ mod feature_tests {
 
     use super::*;
     use geo::Coord;
+    use geo::CoordsIter;
     use geo::Geometry;
     use geo::GeometryCollection;
     use geo::LineString;
@@ -281,6 +675,7 @@ mod feature_tests {
     use geo::Point;
     use geo::Polygon;
     use pretty_assertions::assert_eq;
+    use serde_json::json;
     use topojson::NamedGeometry;
     use topojson::TransformParams;
     use topojson::Value;
@@ -572,41 +967,158 @@ mod feature_tests {
         );
     }
 
-    // tape("topojson.feature top-level geometry collections do not have ids, but second-level geometry collections can", function(test) {
-    //     var t = simpleTopology({type: "GeometryCollection", id: "collection", geometries: [{type: "GeometryCollection", id: "feature", geometries: [{type: "Point", id: "geometry", coordinates: [0, 0]}]}]});
-    //     test.deepEqual(topojson.feature(t, t.objects.foo), {type: "FeatureCollection", features: [{type: "Feature", id: "feature", properties: {}, geometry: {type: "GeometryCollection", geometries: [{type: "Point", coordinates: [0, 0]}]}}]});
-    //     test.end();
-    //   });
+    #[test]
+    fn top_level_gc_do_not_have_ids_but_second_level_gc_can() {
+        println!(
+            "topojson.feature top-level geometry collections do not have ids, but second-level geometry collections can"
+        );
+        let mut point =
+            topojson::Geometry::new(Value::Point(vec![0_f64, 0_f64]));
+        point.id = Some(json!("geometry"));
+        let mut feature =
+            topojson::Geometry::new(Value::GeometryCollection(vec![point]));
+        feature.id = Some(json!("feature"));
+        let mut collection =
+            topojson::Geometry::new(Value::GeometryCollection(vec![feature]));
+        collection.id = Some(json!("collection"));
+        let t = simple_topology(collection);
 
-    //   tape("topojson.feature top-level geometry collections do not have properties, but second-level geometry collections can", function(test) {
-    //     var t = simpleTopology({type: "GeometryCollection", properties: {collection: true}, geometries: [{type: "GeometryCollection", properties: {feature: true}, geometries: [{type: "Point", properties: {geometry: true}, coordinates: [0, 0]}]}]});
-    //     test.deepEqual(topojson.feature(t, t.objects.foo), {type: "FeatureCollection", features: [{type: "Feature", properties: {feature: true}, geometry: {type: "GeometryCollection", geometries: [{type: "Point", coordinates: [0, 0]}]}}]});
-    //     test.end();
-    //   });
+        assert_eq!(
+            feature_object_from_name(&t, "foo"),
+            Some(FeatureObject::FeatureCollection(FeatureCollection {
+                features: vec![Feature {
+                    bbox: None,
+                    geometry: Geometry::GeometryCollection(GeometryCollection(
+                        vec![Geometry::Point(Point(Coord {
+                            x: 0_f64,
+                            y: 0_f64
+                        }))]
+                    )),
+                    id: Some(json!("feature")),
+                    properties: Map::new(),
+                    foreign_members: None,
+                }]
+            }))
+        );
+    }
 
-    //   tape("topojson.feature the object id is promoted to feature id", function(test) {
-    //     var t = simpleTopology({id: "foo", type: "Polygon", arcs: [[0]]});
-    //     test.equal(topojson.feature(t, t.objects.foo).id, "foo");
-    //     test.end();
-    //   });
+    #[test]
+    fn top_level_gc_do_not_have_properties_but_second_level_gc_can() {
+        println!(
+            "topojson.feature top-level geometry collections do not have properties, but second-level geometry collections can"
+        );
+        let mut point =
+            topojson::Geometry::new(Value::Point(vec![0_f64, 0_f64]));
+        point.properties = Some(properties(json!({"geometry": true})));
+        let mut feature =
+            topojson::Geometry::new(Value::GeometryCollection(vec![point]));
+        feature.properties = Some(properties(json!({"feature": true})));
+        let mut collection =
+            topojson::Geometry::new(Value::GeometryCollection(vec![feature]));
+        collection.properties = Some(properties(json!({"collection": true})));
+        let t = simple_topology(collection);
 
-    //   tape("topojson.feature any object properties are promoted to feature properties", function(test) {
-    //     var t = simpleTopology({type: "Polygon", properties: {color: "orange", size: 42}, arcs: [[0]]});
-    //     test.deepEqual(topojson.feature(t, t.objects.foo).properties, {color: "orange", size: 42});
-    //     test.end();
-    //   });
+        assert_eq!(
+            feature_object_from_name(&t, "foo"),
+            Some(FeatureObject::FeatureCollection(FeatureCollection {
+                features: vec![Feature {
+                    bbox: None,
+                    geometry: Geometry::GeometryCollection(GeometryCollection(
+                        vec![Geometry::Point(Point(Coord {
+                            x: 0_f64,
+                            y: 0_f64
+                        }))]
+                    )),
+                    id: None,
+                    properties: properties(json!({"feature": true})),
+                    foreign_members: None,
+                }]
+            }))
+        );
+    }
 
-    //   tape("topojson.feature the object id is optional", function(test) {
-    //     var t = simpleTopology({type: "Polygon", arcs: [[0]]});
-    //     test.equal(topojson.feature(t, t.objects.foo).id, undefined);
-    //     test.end();
-    //   });
+    #[test]
+    fn the_object_id_is_promoted_to_feature_id() {
+        println!("topojson.feature the object id is promoted to feature id");
+        let mut o = topojson::Geometry::new(Value::Polygon(vec![vec![0]]));
+        o.id = Some(json!("foo"));
+        let t = simple_topology(o);
 
-    //   tape("topojson.feature object properties are created if missing", function(test) {
-    //     var t = simpleTopology({type: "Polygon", arcs: [[0]]});
-    //     test.deepEqual(topojson.feature(t, t.objects.foo).properties, {});
-    //     test.end();
-    //   });
+        match feature_object_from_name::<f64>(&t, "foo") {
+            Some(FeatureObject::Feature(f)) => {
+                assert_eq!(f.id, Some(json!("foo")));
+            }
+            _ => panic!("should have returned a feature"),
+        }
+    }
+
+    #[test]
+    fn any_object_properties_are_promoted_to_feature_properties() {
+        println!(
+            "topojson.feature any object properties are promoted to feature properties"
+        );
+        let mut o = topojson::Geometry::new(Value::Polygon(vec![vec![0]]));
+        o.properties = Some(properties(json!({"color": "orange", "size": 42})));
+        let t = simple_topology(o);
+
+        match feature_object_from_name::<f64>(&t, "foo") {
+            Some(FeatureObject::Feature(f)) => {
+                assert_eq!(
+                    f.properties,
+                    properties(json!({"color": "orange", "size": 42}))
+                );
+            }
+            _ => panic!("should have returned a feature"),
+        }
+    }
+
+    #[test]
+    fn the_object_id_is_optional() {
+        println!("topojson.feature the object id is optional");
+        let t = simple_topology(topojson::Geometry::new(Value::Polygon(vec![
+            vec![0],
+        ])));
+
+        match feature_object_from_name::<f64>(&t, "foo") {
+            Some(FeatureObject::Feature(f)) => assert_eq!(f.id, None),
+            _ => panic!("should have returned a feature"),
+        }
+    }
+
+    #[test]
+    fn object_properties_are_created_if_missing() {
+        println!("topojson.feature object properties are created if missing");
+        let t = simple_topology(topojson::Geometry::new(Value::Polygon(vec![
+            vec![0],
+        ])));
+
+        match feature_object_from_name::<f64>(&t, "foo") {
+            Some(FeatureObject::Feature(f)) => {
+                assert_eq!(f.properties, Map::new());
+            }
+            _ => panic!("should have returned a feature"),
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn foreign_members_and_bbox_are_kept() {
+        let mut o = topojson::Geometry::new(Value::Point(vec![0_f64, 0_f64]));
+        o.bbox = Some(vec![0_f64, 0_f64, 0_f64, 0_f64]);
+        o.foreign_members = Some(properties(json!({"source": "survey"})));
+        let t = simple_topology(o);
+
+        assert_eq!(
+            feature_object_from_name(&t, "foo"),
+            Some(FeatureObject::Feature(Feature {
+                bbox: Some(vec![0_f64, 0_f64, 0_f64, 0_f64]),
+                geometry: Geometry::Point(Point(Coord { x: 0_f64, y: 0_f64 })),
+                id: None,
+                properties: Map::new(),
+                foreign_members: Some(properties(json!({"source": "survey"}))),
+            }))
+        );
+    }
 
     #[test]
     fn arcs_are_converted_coordinates() {
@@ -725,7 +1237,7 @@ mod feature_tests {
             objects: vec![NamedGeometry {
                 name: "foo".to_string(),
                 geometry: topojson::Geometry::new(Value::Point(vec![
-                    1_f64, 2_f64,
+                    1_f64, 2_f64, 42_f64,
                 ])),
             }],
             bbox: None,
@@ -736,6 +1248,13 @@ mod feature_tests {
             feature_from_name(&t, "foo"),
             Some(Geometry::Point(Point::new(1_f64, 2_f64)))
         );
+        assert_eq!(
+            feature_z_from_name(&t, "foo"),
+            Some(GeometryZ {
+                geometry: Geometry::Point(Point::new(1_f64, 2_f64)),
+                z: vec![vec![42_f64]],
+            })
+        );
     }
 
     #[test]
@@ -748,7 +1267,7 @@ mod feature_tests {
             objects: vec![NamedGeometry {
                 name: "foo".to_string(),
                 geometry: topojson::Geometry::new(Value::MultiPoint(vec![
-                    vec![1_f64, 2_f64],
+                    vec![1_f64, 2_f64, 42_f64],
                 ])),
             }],
             bbox: None,
@@ -761,6 +1280,10 @@ mod feature_tests {
                 1_f64, 2_f64
             )])))
         );
+        assert_eq!(
+            feature_z_from_name::<f64>(&t, "foo").map(|g| g.z),
+            Some(vec![vec![42_f64]])
+        );
     }
 
     #[test]
@@ -788,7 +1311,89 @@ mod feature_tests {
                 Coord { x: 3_f64, y: 4_f64 }
             ])))
         );
+        assert_eq!(
+            feature_z_from_name::<f64>(&t, "foo").map(|g| g.z),
+            Some(vec![
+                vec![f64::from(0xf00), f64::from(0xbe)],
+                vec![f64::from(0xbae), f64::from(0xef)]
+            ])
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn preserves_additional_dimensions_through_stitched_and_reversed_arcs() {
+        let t = Topology {
+            arcs: vec![
+                vec![vec![0_f64, 0_f64, 10_f64], vec![1_f64, 0_f64, 11_f64]],
+                vec![
+                    vec![1_f64, 0_f64, 11_f64],
+                    vec![1_f64, 1_f64, 12_f64],
+                    vec![0_f64, 0_f64, 10_f64],
+                ],
+                vec![
+                    vec![0_f64, 0_f64, 1_f64],
+                    vec![0_f64, 1_f64, 2_f64],
+                    vec![1_f64, 1_f64, 3_f64],
+                    vec![2_f64, 2_f64, 4_f64],
+                ],
+            ],
+            objects: vec![NamedGeometry {
+                name: "foo".to_string(),
+                geometry: topojson::Geometry::new(Value::GeometryCollection(
+                    vec![
+                        topojson::Geometry::new(Value::Polygon(vec![vec![
+                            0, 1,
+                        ]])),
+                        topojson::Geometry::new(Value::LineString(vec![
+                            -2, -1,
+                        ])),
+                        topojson::Geometry::new(Value::Polygon(vec![vec![2]])),
+                        topojson::Geometry::new(Value::Point(vec![
+                            5_f64, 5_f64,
+                        ])),
+                    ],
+                )),
+            }],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+        let g = feature_z_from_name::<f64>(&t, "foo").expect("foo is present");
+        assert_eq!(
+            g.elevations().collect::<Vec<_>>(),
+            vec![
+                // The stitched ring.
+                Some(10_f64),
+                Some(11_f64),
+                Some(12_f64),
+                Some(10_f64),
+                // The line, both arcs reversed.
+                Some(10_f64),
+                Some(12_f64),
+                Some(11_f64),
+                Some(10_f64),
+                // The ring which had to be closed.
+                Some(1_f64),
+                Some(2_f64),
+                Some(3_f64),
+                Some(4_f64),
+                Some(1_f64),
+                // The point has no elevation.
+                None,
+            ]
+        );
+        assert_eq!(g.z.len(), g.geometry.coords_iter().count());
+        assert_eq!(g.geometry, feature_from_name(&t, "foo").unwrap());
     }
+
+    fn properties(value: JsonValue) -> Map<String, JsonValue> {
+        match value {
+            JsonValue::Object(map) => map,
+            _ => unreachable!("expected a json object"),
+        }
+    }
+
     fn simple_topology(object: topojson::Geometry) -> Topology {
         Topology {
             arcs: vec![
@@ -824,4 +1429,129 @@ mod feature_tests {
             foreign_members: None,
         }
     }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn try_feature_reports_malformed_input() {
+        let t = simple_topology(topojson::Geometry::new(Value::Polygon(vec![
+            vec![0],
+        ])));
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::Polygon(vec![vec![0]])),
+            Ok(feature(&t, &Value::Polygon(vec![vec![0]])))
+        );
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::LineString(vec![!5])),
+            Err(Error::ArcIndexOutOfRange { index: !5, len: 5 })
+        );
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::MultiLineString(vec![vec![]])),
+            Err(Error::EmptyArcIndexes)
+        );
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::Point(vec![0_f64])),
+            Err(Error::InvalidPosition)
+        );
+
+        let mut t = t;
+        t.arcs.push(vec![]);
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::LineString(vec![5])),
+            Err(Error::EmptyArc { arc: 5 })
+        );
+
+        t.transform = Some(TransformParams {
+            scale: [0_f64, 1_f64],
+            translate: [0_f64, 0_f64],
+        });
+        assert_eq!(
+            try_feature::<f64>(&t, &Value::Point(vec![0_f64, 0_f64])),
+            Err(Error::InvalidTransform)
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn objects_can_be_decoded_in_parallel() {
+        const fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Builder<'_>>();
+
+        let t = simple_topology(topojson::Geometry::new(Value::Polygon(vec![
+            vec![0],
+        ])));
+        let geometries = std::thread::scope(|s| {
+            let a =
+                s.spawn(|| feature::<f64>(&t, &t.objects[0].geometry.value));
+            let b =
+                s.spawn(|| feature::<f64>(&t, &t.objects[0].geometry.value));
+            [a.join().unwrap(), b.join().unwrap()]
+        });
+        assert_eq!(geometries[0], geometries[1]);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn feature_par_matches_feature() {
+        let t = simple_topology(topojson::Geometry::new(
+            Value::GeometryCollection(vec![
+                topojson::Geometry::new(Value::Polygon(vec![vec![0]])),
+                topojson::Geometry::new(Value::GeometryCollection(vec![
+                    topojson::Geometry::new(Value::Point(vec![0_f64, 0_f64])),
+                    topojson::Geometry::new(Value::LineString(vec![1, 2])),
+                ])),
+            ]),
+        ));
+        let o = &t.objects[0].geometry.value;
+
+        assert_eq!(feature_par::<f64>(&t, o), feature::<f64>(&t, o));
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn features_iter_yields_each_member_with_its_id_and_properties() {
+        let t = simple_topology(topojson::Geometry::new(
+            Value::GeometryCollection(vec![
+                topojson::Geometry {
+                    id: Some(json!("a")),
+                    properties: Some(properties(json!({"n": 1}))),
+                    ..topojson::Geometry::new(Value::Polygon(vec![vec![0]]))
+                },
+                topojson::Geometry::new(Value::LineString(vec![1, 2])),
+            ]),
+        ));
+
+        assert!(features_iter::<f64>(&t, "bar").is_none());
+
+        let mut iter = features_iter::<f64>(&t, "foo").unwrap();
+        assert_eq!(iter.len(), 2);
+
+        let first = iter.next().unwrap();
+        assert_eq!(first.id, Some(json!("a")));
+        assert_eq!(first.properties, properties(json!({"n": 1})));
+        assert_eq!(first.geometry, feature(&t, &Value::Polygon(vec![vec![0]])));
+
+        let second = iter.next().unwrap();
+        assert_eq!(second.id, None);
+        assert_eq!(second.properties, Map::new());
+        assert_eq!(
+            second.geometry,
+            feature(&t, &Value::LineString(vec![1, 2]))
+        );
+        assert!(iter.next().is_none());
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn features_iter_yields_a_single_feature_for_other_objects() {
+        let t = simple_topology(topojson::Geometry {
+            id: Some(json!(7)),
+            ..topojson::Geometry::new(Value::Polygon(vec![vec![0]]))
+        });
+
+        let features: Vec<Feature<f64>> =
+            features_iter(&t, "foo").unwrap().collect();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].id, Some(json!(7)));
+    }
 }
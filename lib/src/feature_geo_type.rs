@@ -16,7 +16,6 @@ use topojson::Value;
 
 use crate::reverse::reverse;
 use crate::transform::Transform;
-use crate::transform::gen_transform;
 
 /// Given a object name find convert and return a Geometry object.
 ///
@@ -56,7 +55,7 @@ where
 
     Builder {
         arcs: topology.arcs.clone(),
-        transform: gen_transform(&topology.transform),
+        transform: Transform::new(topology.transform.as_ref()),
     }
     .geometry(o)
 }
@@ -71,7 +70,7 @@ impl Builder {
     /// Convert the index found in a Geometry object into a point.
     ///
     /// Using the top level arcs array as reference.
-    fn arc(&mut self, i: i32, points: &mut Vec<(f64, f64)>) {
+    fn arc(&self, i: i32, points: &mut Vec<(f64, f64)>) {
         if !points.is_empty() {
             points.pop();
         }
@@ -80,10 +79,7 @@ impl Builder {
         let index = if i < 0 { !i } else { i } as usize;
         let a = &self.arcs[index];
         let n = a.len();
-        for (k, v) in a.iter().enumerate() {
-            let t = (self.transform)(v, k);
-            points.push((t[0], t[1]));
-        }
+        self.transform.arc(a, points);
 
         if i < 0 {
             reverse(points, n);
@@ -92,15 +88,15 @@ impl Builder {
 
     /// Transform a single point.
     #[inline]
-    fn point(&mut self, p: &[f64]) -> Vec<f64> {
-        (self.transform)(p, 0)
+    fn point(&self, p: &[f64]) -> [f64; 2] {
+        self.transform.point(p)
     }
 
     /// Convert a array of indicies found in a Geometry object into a arrays of
     /// points.
     ///
     /// Using the top level arcs array as reference.
-    fn line(&mut self, arcs: &[i32]) -> Vec<(f64, f64)> {
+    fn line(&self, arcs: &[i32]) -> Vec<(f64, f64)> {
         let mut points: Vec<(f64, f64)> = Vec::with_capacity(arcs.len() + 1);
         for a in arcs {
             self.arc(*a, &mut points);
@@ -114,7 +110,7 @@ impl Builder {
         points
     }
 
-    fn ring(&mut self, arcs: &[i32]) -> Vec<(f64, f64)> {
+    fn ring(&self, arcs: &[i32]) -> Vec<(f64, f64)> {
         let mut points = self.line(arcs);
         // This may happen if an arc has only two points.
         while points.len() < 4 {
@@ -125,7 +121,7 @@ impl Builder {
 
     #[inline]
     fn polygon<'a>(
-        &'a mut self,
+        &'a self,
         arcs: &'a [ArcIndexes],
     ) -> impl Iterator<Item = LineString<f64>> + 'a {
         arcs.iter().map(move |x| self.ring(x)).map(|x| {
@@ -138,7 +134,7 @@ impl Builder {
 
     /// For collections recursively build objects.
     #[inline]
-    fn geometry<T>(&mut self, o: &Value) -> Geometry<T>
+    fn geometry<T>(&self, o: &Value) -> Geometry<T>
     where
         T: CoordFloat,
     {
@@ -0,0 +1,319 @@
+use geo::Geometry;
+use topojson::{ArcIndexes, NamedGeometry, Topology, Value};
+
+use crate::feature::feature;
+use crate::prune::prune;
+use crate::translate;
+
+/// Returns a copy of the topology without the polygon rings rejected by the
+/// filter.
+///
+/// The filter is given the arc indexes of each ring, and whether the ring
+/// is a hole. When the exterior ring of a polygon is rejected the whole
+/// polygon is removed, and so are collections left empty. An object left
+/// with no geometry becomes an empty geometry collection, keeping its id
+/// and properties. Points and lines are kept.
+///
+/// The result is passed to [`prune`](crate::prune::prune), so the arcs which
+/// are no longer referenced are removed and the others renumbered.
+#[must_use]
+pub fn filter<F>(topology: &Topology, mut ring_filter: F) -> Topology
+where
+    F: FnMut(&[i32], bool) -> bool,
+{
+    let objects = topology
+        .objects
+        .iter()
+        .map(|o| NamedGeometry {
+            name: o.name.clone(),
+            geometry: with_value(
+                &o.geometry,
+                filter_value(&o.geometry.value, &mut ring_filter)
+                    .unwrap_or_else(|| Value::GeometryCollection(vec![])),
+            ),
+        })
+        .collect();
+
+    let mut out = Topology {
+        bbox: topology.bbox.clone(),
+        objects,
+        transform: topology.transform.clone(),
+        arcs: topology.arcs.clone(),
+        foreign_members: topology.foreign_members.clone(),
+    };
+    prune(&mut out);
+    out
+}
+
+/// Returns the filtered value, `None` when nothing remains.
+fn filter_value<F>(input: &Value, f: &mut F) -> Option<Value>
+where
+    F: FnMut(&[i32], bool) -> bool,
+{
+    match input {
+        Value::Polygon(rings) => Some(Value::Polygon(filter_rings(rings, f)?)),
+        Value::MultiPolygon(polygons) => {
+            let polygons: Vec<Vec<ArcIndexes>> = polygons
+                .iter()
+                .filter_map(|rings| filter_rings(rings, f))
+                .collect();
+            (!polygons.is_empty()).then_some(Value::MultiPolygon(polygons))
+        }
+        Value::GeometryCollection(gc) => {
+            let geometries: Vec<topojson::Geometry> = gc
+                .iter()
+                .filter_map(|g| Some(with_value(g, filter_value(&g.value, f)?)))
+                .collect();
+            (!geometries.is_empty())
+                .then_some(Value::GeometryCollection(geometries))
+        }
+        _ => Some(input.clone()),
+    }
+}
+
+fn filter_rings<F>(rings: &[ArcIndexes], f: &mut F) -> Option<Vec<ArcIndexes>>
+where
+    F: FnMut(&[i32], bool) -> bool,
+{
+    // If the exterior is small, ignore any holes.
+    let (exterior, interiors) = rings.split_first()?;
+    if !f(exterior, false) {
+        return None;
+    }
+    Some(
+        std::iter::once(exterior.clone())
+            .chain(interiors.iter().filter(|ring| f(ring, true)).cloned())
+            .collect(),
+    )
+}
+
+/// A copy of the geometry, with a new value.
+fn with_value(input: &topojson::Geometry, value: Value) -> topojson::Geometry {
+    topojson::Geometry {
+        bbox: input.bbox.clone(),
+        value,
+        properties: input.properties.clone(),
+        id: input.id.clone(),
+        foreign_members: input.foreign_members.clone(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Owner {
+    Unused,
+    Ring(usize),
+    Shared,
+}
+
+/// Returns a filter for [`filter`] which accepts the rings sharing an arc
+/// with another ring, such as the borders between neighbouring countries.
+///
+/// Combine it with another filter, so that small rings are only removed
+/// when they are detached, islands rather than enclaves.
+pub fn filter_attached(topology: &Topology) -> impl Fn(&[i32], bool) -> bool {
+    fn geometry(o: &Value, owners: &mut [Owner], ring_index: &mut usize) {
+        match o {
+            Value::GeometryCollection(gc) => {
+                for g in gc {
+                    geometry(&g.value, owners, ring_index);
+                }
+            }
+            Value::Polygon(rings) => arcs(rings, owners, ring_index),
+            Value::MultiPolygon(polygons) => {
+                for rings in polygons {
+                    arcs(rings, owners, ring_index);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn arcs(
+        rings: &[ArcIndexes],
+        owners: &mut [Owner],
+        ring_index: &mut usize,
+    ) {
+        for ring in rings {
+            for arc in ring {
+                if let Some(owner) = owners.get_mut(translate(*arc)) {
+                    *owner = match *owner {
+                        Owner::Unused => Owner::Ring(*ring_index),
+                        Owner::Ring(i) if i == *ring_index => Owner::Ring(i),
+                        _ => Owner::Shared,
+                    };
+                }
+            }
+            *ring_index += 1;
+        }
+    }
+
+    let mut owners = vec![Owner::Unused; topology.arcs.len()];
+    let mut ring_index = 0;
+    for o in &topology.objects {
+        geometry(&o.geometry.value, &mut owners, &mut ring_index);
+    }
+
+    move |ring: &[i32], _interior: bool| {
+        ring.iter()
+            .any(|arc| owners.get(translate(*arc)) == Some(&Owner::Shared))
+    }
+}
+
+/// Returns a filter for [`filter`] which accepts the rings whose weight is
+/// at least `min_weight`.
+///
+/// The weight function is given the decoded positions of the closed ring,
+/// and whether it is a hole. Use `|ring, _| planar_ring_area(ring)` for
+/// projected topologies and `spherical_ring_area` for longitude and
+/// latitude, both from the `simplify` module.
+pub fn filter_weight<W>(
+    topology: &Topology,
+    min_weight: f64,
+    weight: W,
+) -> impl Fn(&[i32], bool) -> bool
+where
+    W: Fn(&[[f64; 2]], bool) -> f64,
+{
+    move |ring: &[i32], interior: bool| {
+        let Geometry::Polygon(polygon) =
+            feature::<f64>(topology, &Value::Polygon(vec![ring.to_vec()]))
+        else {
+            unreachable!("a polygon is always converted into a polygon");
+        };
+        let positions: Vec<[f64; 2]> =
+            polygon.exterior().coords().map(|c| [c.x, c.y]).collect();
+        weight(&positions, interior) >= min_weight
+    }
+}
+
+#[cfg(test)]
+mod filter_tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::simplify::planar_ring_area;
+
+    fn square(x: f64, y: f64, size: f64) -> Vec<Vec<f64>> {
+        vec![
+            vec![x, y],
+            vec![x, y + size],
+            vec![x + size, y + size],
+            vec![x + size, y],
+            vec![x, y],
+        ]
+    }
+
+    fn member(id: &str, value: Value) -> topojson::Geometry {
+        let mut g = topojson::Geometry::new(value);
+        g.id = Some(json!(id));
+        g
+    }
+
+    ///
+    /// +----+----+  +----+  +-------------+
+    /// | a  | b  |  | c  |  | d    +-+    |
+    /// +----+----+  +----+  |      +-+    |
+    ///                      +-------------+
+    ///
+    fn topology() -> Topology {
+        Topology {
+            arcs: vec![
+                vec![vec![1_f64, 1_f64], vec![1_f64, 0_f64]],
+                vec![
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![2_f64, 1_f64],
+                    vec![2_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                ],
+                square(3_f64, 0_f64, 1_f64),
+                square(10_f64, 0_f64, 10_f64),
+                square(14_f64, 4_f64, 1_f64),
+            ],
+            objects: vec![
+                NamedGeometry {
+                    name: "city".to_string(),
+                    geometry: topojson::Geometry::new(Value::Point(vec![
+                        3.5_f64, 0.5_f64,
+                    ])),
+                },
+                NamedGeometry {
+                    name: "land".to_string(),
+                    geometry: topojson::Geometry::new(
+                        Value::GeometryCollection(vec![
+                            member("a", Value::Polygon(vec![vec![0, 1]])),
+                            member("b", Value::Polygon(vec![vec![-1, 2]])),
+                            member("c", Value::Polygon(vec![vec![3]])),
+                            member("d", Value::Polygon(vec![vec![4], vec![5]])),
+                        ]),
+                    ),
+                },
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn removes_small_rings() {
+        let topology = topology();
+        let filtered = filter(
+            &topology,
+            filter_weight(&topology, 1.5_f64, |ring, _| planar_ring_area(ring)),
+        );
+
+        assert_eq!(filtered.arcs, vec![topology.arcs[4].clone()]);
+        assert_eq!(filtered.objects[0], topology.objects[0]);
+        assert_eq!(
+            filtered.objects[1].geometry.value,
+            Value::GeometryCollection(vec![member(
+                "d",
+                Value::Polygon(vec![vec![0]])
+            )])
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn protects_attached_rings() {
+        let topology = topology();
+        let attached = filter_attached(&topology);
+        let weight =
+            filter_weight(&topology, 1.5_f64, |ring, _| planar_ring_area(ring));
+        let filtered = filter(&topology, |ring, interior| {
+            attached(ring, interior) || weight(ring, interior)
+        });
+
+        assert_eq!(
+            filtered.objects[1].geometry.value,
+            Value::GeometryCollection(vec![
+                member("a", Value::Polygon(vec![vec![0, 1]])),
+                member("b", Value::Polygon(vec![vec![-1, 2]])),
+                member("d", Value::Polygon(vec![vec![3]])),
+            ])
+        );
+        assert_eq!(filtered.arcs.len(), 4);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn keeps_emptied_objects() {
+        let topology = topology();
+        let filtered = filter(&topology, |_, _| false);
+
+        assert!(filtered.arcs.is_empty());
+        assert_eq!(filtered.objects[0], topology.objects[0]);
+        assert_eq!(
+            filtered.objects[1].geometry,
+            topojson::Geometry::new(Value::GeometryCollection(vec![]))
+        );
+    }
+}
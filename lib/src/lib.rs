@@ -23,23 +23,48 @@ extern crate pretty_assertions;
 extern crate topojson;
 
 /// Bounding Box.
-mod bbox;
+pub mod bbox;
 mod bisect;
+/// `DecodedTopology`, a topology whose arcs are decoded once, up front.
+pub mod decoded;
+/// functions `dissolve()` and `dissolve_object()`, merges grouped by key.
+pub mod dissolve;
+/// The crate's `Error` type, and checks made by the `try_` functions.
+mod error;
+pub use error::Error;
 /// function `feature()` and various From implementations.
 pub mod feature;
+/// functions `filter()`, `filter_attached()` and `filter_weight()`.
+pub mod filter;
 
+/// functions `load()` and `load_objects()`, a streaming topology reader.
+pub mod load;
 /// Identifies neighbors in geometry.
 pub mod neighbors;
 
 mod feature_geo_type;
-mod merge;
-mod mesh;
+/// functions `merge()` and `merge_arcs()`.
+pub mod merge;
+/// functions `mesh()` and `mesh_arcs()`.
+pub mod mesh;
 mod polygon_u;
+/// function `prune()`, removes the arcs no object references.
+pub mod prune;
+/// function `quantize()` and unit tests.
+pub mod quantize;
 /// function `reverse()` and unit tests.
 mod reverse;
+/// functions `presimplify()`, `simplify()`, `quantile()` and area weights.
+pub mod simplify;
 mod stitch;
-/// function generate, helper type `TransformFn` and unit tests.
+/// function `topology()`, builds a topology from geo geometries.
+pub mod topology;
+/// types `Transform` and `Decoder`, and unit tests.
 mod transform;
+/// types `Untransform` and `Encoder`, and unit tests.
+pub mod untransform;
+/// function `validate()`, checks a topology against the specification.
+pub mod validate;
 
 /// Translate ARC indexes.
 ///
@@ -0,0 +1,569 @@
+use std::fmt;
+use std::io::Read;
+
+use serde::Deserializer;
+use serde::de::{
+    DeserializeSeed, Error as _, IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+use topojson::{Arc, Bbox, NamedGeometry, Topology, TransformParams};
+
+use crate::transform::Transform;
+
+/// The arcs of a topology, stored in one flat buffer of positions.
+///
+/// Only the first two dimensions of each position are kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlatArcs {
+    positions: Vec<[f64; 2]>,
+    // The positions of arc `i` are `positions[offsets[i]..offsets[i + 1]]`.
+    offsets: Vec<usize>,
+}
+
+impl FlatArcs {
+    /// The number of arcs.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// True when there are no arcs.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the positions of an arc, or `None` if the arc does not exist.
+    #[must_use]
+    pub fn get(&self, i: usize) -> Option<&[[f64; 2]]> {
+        let start = *self.offsets.get(i)?;
+        let end = *self.offsets.get(i + 1)?;
+        Some(&self.positions[start..end])
+    }
+
+    /// Iterates over the arcs.
+    #[must_use]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &[[f64; 2]]> {
+        self.offsets.windows(2).map(|w| &self.positions[w[0]..w[1]])
+    }
+
+    fn push(&mut self, p: [f64; 2]) {
+        self.positions.push(p);
+    }
+
+    fn end_arc(&mut self) {
+        self.offsets.push(self.positions.len());
+    }
+}
+
+/// The arcs of either kind of topology.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ArcTable<'a> {
+    Nested(&'a [Arc]),
+    Flat(&'a FlatArcs),
+}
+
+impl<'a> ArcTable<'a> {
+    pub(crate) const fn len(&self) -> usize {
+        match self {
+            Self::Nested(arcs) => arcs.len(),
+            Self::Flat(arcs) => arcs.len(),
+        }
+    }
+
+    /// The number of positions in the arc, `None` if it does not exist.
+    pub(crate) fn arc_len(&self, i: usize) -> Option<usize> {
+        match self {
+            Self::Nested(arcs) => arcs.get(i).map(Vec::len),
+            Self::Flat(arcs) => arcs.get(i).map(<[[f64; 2]]>::len),
+        }
+    }
+
+    /// True when every position of the arc has at least two coordinates.
+    pub(crate) fn arc_has_valid_positions(&self, i: usize) -> bool {
+        match self {
+            Self::Nested(arcs) => arcs[i].iter().all(|p| p.len() >= 2),
+            // Enforced when loading.
+            Self::Flat(_) => true,
+        }
+    }
+
+    /// Appends the dimensions beyond x and y of each position of the arc to
+    /// `out`. A flat arc table holds none, so those are empty.
+    ///
+    /// Returns the number of positions appended.
+    pub(crate) fn extra(&self, i: usize, out: &mut Vec<&'a [f64]>) -> usize {
+        match self {
+            Self::Nested(arcs) => {
+                out.extend(arcs[i].iter().map(|p| &p[2..]));
+                arcs[i].len()
+            }
+            Self::Flat(arcs) => {
+                let n = arcs.offsets[i + 1] - arcs.offsets[i];
+                out.extend(std::iter::repeat_n(&[][..], n));
+                n
+            }
+        }
+    }
+
+    /// Decodes the arc, appending its positions to `out`.
+    ///
+    /// Returns the number of positions appended.
+    pub(crate) fn decode(
+        &self,
+        i: usize,
+        transform: &Transform,
+        out: &mut Vec<(f64, f64)>,
+    ) -> usize {
+        match self {
+            Self::Nested(arcs) => {
+                transform.arc(&arcs[i], out);
+                arcs[i].len()
+            }
+            Self::Flat(arcs) => {
+                let arc = &arcs.positions[arcs.offsets[i]..arcs.offsets[i + 1]];
+                transform.arc(arc, out);
+                arc.len()
+            }
+        }
+    }
+}
+
+impl From<&[Arc]> for FlatArcs {
+    fn from(arcs: &[Arc]) -> Self {
+        let mut flat = Self {
+            positions: Vec::with_capacity(arcs.iter().map(Vec::len).sum()),
+            offsets: Vec::with_capacity(arcs.len() + 1),
+        };
+        flat.offsets.push(0);
+        for arc in arcs {
+            for p in arc {
+                flat.push([p[0], p[1]]);
+            }
+            flat.end_arc();
+        }
+        flat
+    }
+}
+
+/// A topology loaded by [`load`], with its arcs held in a [`FlatArcs`].
+///
+/// Foreign members of the topology are not kept. Use
+/// `feature::feature_flat()` to decode its objects.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FlatTopology {
+    /// The bounding box of the topology.
+    pub bbox: Option<Bbox>,
+    /// The objects which were loaded, sorted by name.
+    pub objects: Vec<NamedGeometry>,
+    /// The quantization transform.
+    pub transform: Option<TransformParams>,
+    /// The arcs.
+    pub arcs: FlatArcs,
+}
+
+impl FlatTopology {
+    /// Returns the object with the given name.
+    #[must_use]
+    pub fn object(&self, name: &str) -> Option<&NamedGeometry> {
+        self.objects.iter().find(|o| o.name == name)
+    }
+}
+
+impl From<&Topology> for FlatTopology {
+    fn from(topology: &Topology) -> Self {
+        Self {
+            bbox: topology.bbox.clone(),
+            objects: topology.objects.clone(),
+            transform: topology.transform.clone(),
+            arcs: FlatArcs::from(topology.arcs.as_slice()),
+        }
+    }
+}
+
+/// Reads a topology from any reader, without first holding the whole
+/// document in memory.
+///
+/// The reader is read unbuffered, wrap files in a `std::io::BufReader`.
+///
+/// # Errors
+///
+/// When the input is not valid JSON, or is not a `TopoJSON` topology.
+pub fn load<R: Read>(reader: R) -> serde_json::Result<FlatTopology> {
+    load_with(reader, None)
+}
+
+/// Reads a topology from any reader, keeping only the named objects.
+///
+/// The other objects are parsed but never built, see [`load`].
+///
+/// # Errors
+///
+/// When the input is not valid JSON, or is not a `TopoJSON` topology.
+pub fn load_objects<R: Read>(
+    reader: R,
+    names: &[&str],
+) -> serde_json::Result<FlatTopology> {
+    load_with(reader, Some(names))
+}
+
+fn load_with<R: Read>(
+    reader: R,
+    names: Option<&[&str]>,
+) -> serde_json::Result<FlatTopology> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let mut topology = de.deserialize_map(TopologyVisitor { names })?;
+    de.end()?;
+    topology.objects.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(topology)
+}
+
+struct TopologyVisitor<'a> {
+    names: Option<&'a [&'a str]>,
+}
+
+impl<'de> Visitor<'de> for TopologyVisitor<'_> {
+    type Value = FlatTopology;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a TopoJSON topology")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<FlatTopology, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut topology = FlatTopology::default();
+        let mut type_ = None;
+        let mut has_arcs = false;
+        let mut has_objects = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "type" => type_ = Some(map.next_value::<String>()?),
+                "bbox" => topology.bbox = Some(map.next_value()?),
+                "transform" => {
+                    if topology.transform.is_some() {
+                        return Err(A::Error::duplicate_field("transform"));
+                    }
+                    topology.transform = Some(transform(map.next_value()?)?);
+                }
+                "arcs" => {
+                    if has_arcs {
+                        return Err(A::Error::duplicate_field("arcs"));
+                    }
+                    has_arcs = true;
+                    map.next_value_seed(ArcsSeed(&mut topology.arcs))?;
+                }
+                "objects" => {
+                    if has_objects {
+                        return Err(A::Error::duplicate_field("objects"));
+                    }
+                    has_objects = true;
+                    topology.objects =
+                        map.next_value_seed(ObjectsSeed { names: self.names })?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        match type_.as_deref() {
+            Some("Topology") => {}
+            Some(other) => {
+                return Err(A::Error::custom(format!(
+                    "expected type Topology, found {other}"
+                )));
+            }
+            None => return Err(A::Error::missing_field("type")),
+        }
+        if !has_objects {
+            return Err(A::Error::missing_field("objects"));
+        }
+        if !has_arcs {
+            return Err(A::Error::missing_field("arcs"));
+        }
+        Ok(topology)
+    }
+}
+
+/// `TransformParams::deserialize()` expects the whole topology, so wrap the
+/// transform up as one.
+fn transform<E: serde::de::Error>(
+    value: serde_json::Value,
+) -> Result<TransformParams, E> {
+    let mut topology = serde_json::Map::new();
+    topology.insert(String::from("transform"), value);
+    TransformParams::from_json_object(topology).map_err(E::custom)
+}
+
+struct ObjectsSeed<'a> {
+    names: Option<&'a [&'a str]>,
+}
+
+impl<'de> DeserializeSeed<'de> for ObjectsSeed<'_> {
+    type Value = Vec<NamedGeometry>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ObjectsSeed<'_> {
+    type Value = Vec<NamedGeometry>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a map of TopoJSON objects")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut objects = vec![];
+        while let Some(name) = map.next_key::<String>()? {
+            if self
+                .names
+                .is_none_or(|names| names.contains(&name.as_str()))
+            {
+                objects.push(NamedGeometry {
+                    name,
+                    geometry: map.next_value()?,
+                });
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(objects)
+    }
+}
+
+/// Appends each arc to the buffer.
+struct ArcsSeed<'a>(&'a mut FlatArcs);
+
+impl<'de> DeserializeSeed<'de> for ArcsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArcsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an array of arcs")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        self.0.offsets.push(0);
+        while seq.next_element_seed(ArcSeed(self.0))?.is_some() {
+            self.0.end_arc();
+        }
+        Ok(())
+    }
+}
+
+/// Appends the positions of one arc to the buffer.
+struct ArcSeed<'a>(&'a mut FlatArcs);
+
+impl<'de> DeserializeSeed<'de> for ArcSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArcSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an array of positions")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while let Some(p) = seq.next_element_seed(PositionSeed)? {
+            self.0.push(p);
+        }
+        Ok(())
+    }
+}
+
+/// Reads a position, skipping any dimension beyond the first two.
+struct PositionSeed;
+
+impl<'de> DeserializeSeed<'de> for PositionSeed {
+    type Value = [f64; 2];
+
+    fn deserialize<D>(self, deserializer: D) -> Result<[f64; 2], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PositionSeed {
+    type Value = [f64; 2];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "a position of at least two numbers")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<[f64; 2], A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let x = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+        let y = seq
+            .next_element()?
+            .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+        while seq.next_element::<IgnoredAny>()?.is_some() {}
+        Ok([x, y])
+    }
+}
+
+#[cfg(test)]
+mod load_tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::Error;
+    use crate::feature::{feature, feature_flat, try_feature_flat};
+
+    fn read(path: &str) -> Topology {
+        let file = File::open(path).expect("Could not load json file.");
+        serde_json::from_reader(file).expect("Did not parse correctly.")
+    }
+
+    fn stream(path: &str) -> FlatTopology {
+        let file = File::open(path).expect("Could not load json file.");
+        load(BufReader::new(file)).expect("Did not parse correctly.")
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn loads_the_same_topology_as_serde() {
+        for path in [
+            "./tests/topojson/polygon.json",
+            "./tests/topojson/polygon-q1e4.json",
+            "./tests/topojson/properties.json",
+            "./tests/topojson/points-q1e5.json",
+            "./tests/topojson/empty.json",
+        ] {
+            let topology = read(path);
+            let flat = stream(path);
+            assert_eq!(flat, FlatTopology::from(&topology), "{path}");
+
+            for o in &topology.objects {
+                let value =
+                    &flat.object(&o.name).expect("object").geometry.value;
+                assert_eq!(
+                    feature_flat::<f64>(&flat, value),
+                    feature::<f64>(&topology, &o.geometry.value),
+                    "{path}"
+                );
+            }
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn stores_arcs_in_one_buffer() {
+        let input = r#"{
+            "type": "Topology",
+            "objects": {},
+            "arcs": [[[0, 0, 7], [1, 2]], [], [[3, 4], [5, 6], [7, 8]]]
+        }"#;
+        let flat = load(input.as_bytes()).expect("should load");
+
+        assert_eq!(flat.arcs.len(), 3);
+        assert!(!flat.arcs.is_empty());
+        assert_eq!(
+            flat.arcs.get(0),
+            Some(&[[0_f64, 0_f64], [1_f64, 2_f64]][..])
+        );
+        assert_eq!(flat.arcs.get(1), Some(&[][..]));
+        assert_eq!(flat.arcs.get(3), None);
+        assert_eq!(
+            flat.arcs.iter().map(<[[f64; 2]]>::len).collect::<Vec<_>>(),
+            vec![2, 0, 3]
+        );
+        assert!(FlatArcs::default().is_empty());
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn skips_objects_which_are_not_requested() {
+        let path = "./tests/topojson/points-q1e5.json";
+        let topology = read(path);
+        let name = topology.objects[0].name.as_str();
+
+        let file = File::open(path).expect("Could not load json file.");
+        let flat = load_objects(BufReader::new(file), &[name, "missing"])
+            .expect("Did not parse correctly.");
+        assert_eq!(flat.objects.len(), 1);
+        assert_eq!(flat.objects[0], topology.objects[0]);
+        assert_eq!(flat.arcs.len(), topology.arcs.len());
+
+        let flat = load_objects(BufReader::new(File::open(path).unwrap()), &[])
+            .expect("Did not parse correctly.");
+        assert!(flat.objects.is_empty());
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn rejects_documents_which_are_not_topologies() {
+        for input in [
+            r#"{"type": "FeatureCollection", "objects": {}, "arcs": []}"#,
+            r#"{"objects": {}, "arcs": []}"#,
+            r#"{"type": "Topology", "arcs": []}"#,
+            r#"{"type": "Topology", "objects": {}}"#,
+            r#"{"type": "Topology", "objects": {}, "arcs": [[[0]]]}"#,
+            r#"{"type": "Topology", "objects": {}, "arcs": [], "transform": {"scale": [1, 1]}}"#,
+            r#"{"type": "Topology", "objects": {}, "arcs": []} []"#,
+            r#"{"type": "Topology", "objects": {}, "arcs": [], "arcs": []}"#,
+            r#"{"type": "Topology", "objects": {}, "objects": {}, "arcs": []}"#,
+            r#"{"type": "Topology", "objects": {}, "arcs": [], "transform": {"scale": [1, 1], "translate": [0, 0]}, "transform": {"scale": [1, 1], "translate": [0, 0]}}"#,
+            "[]",
+        ] {
+            assert!(load(input.as_bytes()).is_err(), "{input}");
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn try_feature_flat_reports_malformed_input() {
+        let input = r#"{
+            "type": "Topology",
+            "objects": {"a": {"type": "LineString", "arcs": [3]}},
+            "arcs": [[[0, 0], [1, 1]]]
+        }"#;
+        let flat = load(input.as_bytes()).expect("should load");
+        assert_eq!(
+            try_feature_flat::<f64>(&flat, &flat.objects[0].geometry.value),
+            Err(Error::ArcIndexOutOfRange { index: 3, len: 1 })
+        );
+    }
+}
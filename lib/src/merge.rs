@@ -3,10 +3,18 @@ use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::rc::Rc;
 
-use geo::{Coord, CoordFloat, Geometry};
+use geo::{Coord, CoordFloat, Geometry, Polygon};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde_json::Value as JsonValue;
 use topojson::{ArcIndexes, NamedGeometry, Topology, Value};
 
+use crate::error::Check;
+use crate::error::Error;
+use crate::error::type_name;
 use crate::feature::feature;
+#[cfg(feature = "rayon")]
+use crate::feature::feature_par;
 use crate::polygon_u::PolygonU;
 use crate::stitch::stitch;
 use crate::translate;
@@ -28,13 +36,230 @@ where
 
 /// Given a topology and list of objects, merge the selected objected together, translate and output
 /// a resulting object as `geo_types::Geometry` object.
+///
+/// Points and lines are ignored, see [`skipped`].
+#[must_use]
 pub fn merge<T>(topology: &Topology, objects: &[NamedGeometry]) -> Geometry<T>
 where
     T: CoordFloat + Debug,
 {
-    let mut ma = MergeArcs::new(topology);
+    feature(topology, &merge_arcs(topology, objects).value)
+}
+
+/// Given a topology and list of objects, merge the selected objected together, translate and output
+/// a resulting object as `geo_types::Geometry` object.
+///
+/// The fallible version of [`merge`]. Unlike [`merge`], points and lines are
+/// rejected rather than ignored, [`skipped`] lists them.
+///
+/// # Errors
+///
+/// When a polygon references arcs which do not exist or are empty, when the
+/// transform is malformed, or when a member is not a polygon, a
+/// multi-polygon or a collection of them.
+pub fn try_merge<T>(
+    topology: &Topology,
+    objects: &[NamedGeometry],
+) -> Result<Geometry<T>, Error>
+where
+    T: CoordFloat + Debug,
+{
+    let mut check = Check::new(topology, 2)?;
+    for o in objects {
+        check_polygonal(&mut check, &o.geometry.value)?;
+    }
+    Ok(merge(topology, objects))
+}
+
+fn check_polygonal(check: &mut Check, o: &Value) -> Result<(), Error> {
+    match o {
+        Value::GeometryCollection(gc) => {
+            for g in gc {
+                check_polygonal(check, &g.value)?;
+            }
+            Ok(())
+        }
+        Value::Polygon(_) | Value::MultiPolygon(_) => check.geometry(o),
+        _ => Err(Error::UnexpectedGeometry {
+            expected: "Polygon or MultiPolygon",
+            found: type_name(o),
+        }),
+    }
+}
+
+/// A member of the objects given to [`merge`] which was ignored, because it
+/// is a point or a line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Skipped {
+    /// The name of the object.
+    pub object: String,
+    /// The indexes of the member within nested geometry collections, empty
+    /// when the object itself was ignored.
+    pub path: Vec<usize>,
+    /// The type of the member.
+    pub found: &'static str,
+}
+
+/// Returns the members of the objects which [`merge`] ignores, and which
+/// [`try_merge`] rejects.
+#[must_use]
+pub fn skipped(objects: &[NamedGeometry]) -> Vec<Skipped> {
+    fn walk(
+        object: &str,
+        o: &Value,
+        path: &mut Vec<usize>,
+        out: &mut Vec<Skipped>,
+    ) {
+        match o {
+            Value::GeometryCollection(gc) => {
+                for (i, g) in gc.iter().enumerate() {
+                    path.push(i);
+                    walk(object, &g.value, path, out);
+                    path.pop();
+                }
+            }
+            Value::Polygon(_) | Value::MultiPolygon(_) => {}
+            _ => out.push(Skipped {
+                object: object.to_string(),
+                path: path.clone(),
+                found: type_name(o),
+            }),
+        }
+    }
+
+    let mut out = vec![];
+    for o in objects {
+        walk(&o.name, &o.geometry.value, &mut vec![], &mut out);
+    }
+    out
+}
+
+/// Given a topology and list of objects, merge the selected objects together
+/// and output the result as a `TopoJSON` `MultiPolygon`.
+///
+/// The arc indexes refer to the arcs of the topology, so the result can be
+/// inserted into the topology as a new object. Points and lines are
+/// ignored.
+#[must_use]
+pub fn merge_arcs(
+    topology: &Topology,
+    objects: &[NamedGeometry],
+) -> topojson::Geometry {
+    let polygon_arcs = exterior_arcs(objects)
+        .0
+        .into_iter()
+        .map(|group| stitch_group(topology, group.arcs))
+        .filter(|arcs| !arcs.is_empty())
+        .collect();
+
+    topojson::Geometry::new(Value::MultiPolygon(polygon_arcs))
+}
+
+/// Given a topology and list of objects, merge the selected objected together, translate and output
+/// a resulting object as `geo_types::Geometry` object.
+///
+/// The parallel version of [`merge`], each group of connected polygons is
+/// stitched, and the polygons of the result are converted, on the rayon
+/// thread pool.
+#[cfg(feature = "rayon")]
+#[must_use]
+pub fn merge_par<T>(
+    topology: &Topology,
+    objects: &[NamedGeometry],
+) -> Geometry<T>
+where
+    T: CoordFloat + Debug + Send,
+{
+    let polygon_arcs = exterior_arcs(objects)
+        .0
+        .into_par_iter()
+        .map(|group| stitch_group(topology, group.arcs))
+        .filter(|arcs| !arcs.is_empty())
+        .collect();
+
+    feature_par(topology, &Value::MultiPolygon(polygon_arcs))
+}
+
+/// A polygon of the result of [`merge_with_provenance`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MergedPolygon<T>
+where
+    T: CoordFloat,
+{
+    /// The merged polygon.
+    pub polygon: Polygon<T>,
+    /// The members of the objects which were merged to form the polygon,
+    /// in the order they were given.
+    pub sources: Vec<Source>,
+}
+
+/// A member of the objects given to [`merge_with_provenance`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+    /// The name of the object.
+    pub object: String,
+    /// The indexes of the member within nested geometry collections, empty
+    /// when it is the object itself.
+    pub path: Vec<usize>,
+    /// The id of the member, if any.
+    pub id: Option<JsonValue>,
+}
+
+/// Given a topology and list of objects, merge the selected objected
+/// together, and report which of them formed each resulting polygon.
+///
+/// The polygons are those of the `MultiPolygon` returned by [`merge`], in
+/// the same order. A multi-polygon member whose polygons are not connected
+/// is reported as a source of several polygons.
+#[must_use]
+pub fn merge_with_provenance<T>(
+    topology: &Topology,
+    objects: &[NamedGeometry],
+) -> Vec<MergedPolygon<T>>
+where
+    T: CoordFloat,
+{
+    let (groups, sources) = exterior_arcs(objects);
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let rings = stitch_group(topology, group.arcs);
+            if rings.is_empty() {
+                return None;
+            }
+            let Geometry::Polygon(polygon) =
+                feature(topology, &Value::Polygon(rings))
+            else {
+                unreachable!("a polygon is always converted into a polygon");
+            };
+            Some(MergedPolygon {
+                polygon,
+                sources: group
+                    .sources
+                    .into_iter()
+                    .map(|i| sources[i].clone())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// The exterior arcs of a group of connected polygons.
+struct Group {
+    arcs: ArcIndexes,
+    /// Indexes into the sources returned by `exterior_arcs()`, ascending.
+    sources: Vec<usize>,
+}
+
+/// Groups the polygons of the objects into connected components, and
+/// returns the exterior arcs of each group, and the members of the objects
+/// the polygons were taken from.
+fn exterior_arcs(objects: &[NamedGeometry]) -> (Vec<Group>, Vec<Source>) {
+    let mut ma = MergeArcs::new();
 
-    objects.iter().for_each(|o| ma.geometry(&o.geometry));
+    for o in objects {
+        ma.geometry(&o.name, &o.geometry, &mut vec![]);
+    }
 
     ma.polygons.clone().iter().for_each(|polygon| {
         if polygon.borrow().is_not_marked() {
@@ -68,7 +293,7 @@ where
         .for_each(|polygon| polygon.borrow_mut().unmark());
 
     // Extract the exterior (unique) arcs.
-    let polygon_arcs = ma
+    let groups = ma
         .groups
         .iter()
         .map(|polygons| {
@@ -84,37 +309,49 @@ where
                     }
                 });
             }
-
-            // Stich the arc into one or more rings.
-            let mut arcs = stitch(topology, arcs);
-            // If more than one ring is returned, at most one of these
-            // rings can be the exterior; choose the one with the
-            // greatest absolute area.
-            let n = arcs.len();
-            if n > 1 {
-                let mut iter_mut = arcs.iter_mut();
-                let mut k = ma.area(iter_mut.next().unwrap().clone());
-                let mut ki;
-                for i in 1..arcs.len() {
-                    ki = ma.area(arcs[i].clone());
-                    if ki > k {
-                        arcs.swap(0, i);
-                        k = ki;
-                    }
-                }
-            }
-            arcs
+            let mut sources: Vec<usize> =
+                polygons.iter().map(|polygon| polygon.source).collect();
+            sources.sort_unstable();
+            sources.dedup();
+            Group { arcs, sources }
         })
-        .filter(|arcs| !(*arcs).is_empty())
         .collect();
-    let ma = Value::MultiPolygon(polygon_arcs);
 
-    // let merge_arcs = ma.generate(objects);
-    feature(topology, &ma)
+    (groups, ma.sources)
+}
+
+/// Stich the exterior arcs of a group into one or more rings.
+fn stitch_group(topology: &Topology, arcs: ArcIndexes) -> Vec<ArcIndexes> {
+    let mut arcs = stitch(topology, arcs);
+    // If more than one ring is returned, at most one of these
+    // rings can be the exterior; choose the one with the
+    // greatest absolute area.
+    let n = arcs.len();
+    if n > 1 {
+        let mut k = area(topology, &arcs[0]);
+        let mut ki;
+        for i in 1..arcs.len() {
+            ki = area(topology, &arcs[i]);
+            if ki > k {
+                arcs.swap(0, i);
+                k = ki;
+            }
+        }
+    }
+    arcs
+}
+
+fn area(topology: &Topology, ring: &[i32]) -> f64 {
+    let polygon = Value::Polygon(vec![ring.to_vec()]);
+    match feature(topology, &polygon) {
+        Geometry::Polygon(p) => planar_ring_area(&p.exterior().0),
+        // A polygon always decodes to a polygon.
+        _ => 0_f64,
+    }
 }
 
 #[derive(Debug)]
-struct MergeArcs<'a> {
+struct MergeArcs {
     // Rc<RefCell<_>> A Shared reference is needed here because changes to
     // the contents of the 'polygon' refcell should be observed in multiple
     // rows of the polygons_by_arc table.
@@ -122,46 +359,74 @@ struct MergeArcs<'a> {
     polygons_by_arc: BTreeMap<usize, Vec<Rc<RefCell<PolygonU>>>>,
 
     groups: Vec<Vec<PolygonU>>,
-    topology: &'a Topology,
+
+    /// The members of the objects, indexed by `PolygonU::source`.
+    sources: Vec<Source>,
 }
 
-impl<'a> MergeArcs<'a> {
-    const fn new(topology: &'a Topology) -> Self {
+impl MergeArcs {
+    const fn new() -> Self {
         Self {
             polygons: vec![],
             polygons_by_arc: BTreeMap::new(),
             groups: vec![],
-            topology,
+            sources: vec![],
         }
     }
 
     // Process collections of items - 'extract'ing all sub items.
-    fn geometry(&mut self, o: &topojson::Geometry) {
-        // let value = o.value;
+    fn geometry(
+        &mut self,
+        object: &str,
+        o: &topojson::Geometry,
+        path: &mut Vec<usize>,
+    ) {
         match &o.value {
             Value::GeometryCollection(gc) => {
-                for g in gc {
-                    self.geometry(g);
+                for (i, g) in gc.iter().enumerate() {
+                    path.push(i);
+                    self.geometry(object, g, path);
+                    path.pop();
                 }
             }
-            Value::Polygon(polygon) => self.extract(polygon),
+            Value::Polygon(polygon) => {
+                let source = self.source(object, o, path);
+                self.extract(polygon, source);
+            }
             Value::MultiPolygon(mp) => {
+                let source = self.source(object, o, path);
                 for p in mp {
-                    self.extract(p);
+                    self.extract(p, source);
                 }
             }
-            // Ignore ValuesMultiLines, Values::Lines,  Values::Points etc.
-            _ => {
-                panic!("unprocessed object");
-            }
+            // Ignore Values::MultiLineString, Values::LineString,
+            // Values::Point etc.
+            _ => {}
         }
     }
 
+    /// Records the member the next polygons are taken from.
+    fn source(
+        &mut self,
+        object: &str,
+        o: &topojson::Geometry,
+        path: &[usize],
+    ) -> usize {
+        self.sources.push(Source {
+            object: object.to_string(),
+            path: path.to_vec(),
+            id: o.id.clone(),
+        });
+        self.sources.len() - 1
+    }
+
     /// Loop over the input pushing to internal state.
     /// `polygons_by_arc` and polygons.
-    fn extract(&mut self, polygon: &[Vec<i32>]) {
+    fn extract(&mut self, polygon: &[Vec<i32>], source: usize) {
         // Value to be stored and referred to .. in pba
-        let pu = Rc::new(RefCell::new(PolygonU::from(polygon.to_vec())));
+        let mut pu = PolygonU::from(polygon.to_vec());
+        pu.source = source;
+        let pu = Rc::new(RefCell::new(pu));
 
         for ring in polygon {
             for arc in ring {
@@ -177,31 +442,29 @@ impl<'a> MergeArcs<'a> {
 
         self.polygons.push(pu);
     }
-
-    fn area(&self, ring: ArcIndexes) -> f64 {
-        let polygon = Value::Polygon(vec![ring]);
-        let object = feature(self.topology, &polygon);
-        match object {
-            Geometry::Polygon(p) => planar_ring_area(&p.exterior().0),
-            _ => {
-                todo!("was expecting a polygon");
-            }
-        }
-    }
 }
 
 #[cfg(test)]
 mod merge_tests {
+    use std::fs::File;
+
     use geo::Geometry;
     use geo::LineString;
     use geo::MultiPolygon;
     use geo::Polygon;
     use pretty_assertions::assert_eq;
+    use serde_json::json;
     use topojson::NamedGeometry;
     use topojson::Topology;
     use topojson::Value;
 
+    use crate::Error;
+    use crate::merge::Skipped;
     use crate::merge::merge;
+    use crate::merge::merge_arcs;
+    use crate::merge::merge_with_provenance;
+    use crate::merge::skipped;
+    use crate::merge::try_merge;
 
     #[test]
     fn merge_ignores_null_geometries() {
@@ -281,6 +544,12 @@ mod merge_tests {
         )]));
 
         assert_eq!(merge(&topology, &objects), mp);
+        assert_eq!(
+            merge_arcs(&topology, &objects),
+            topojson::Geometry::new(Value::MultiPolygon(vec![vec![vec![
+                1, 2
+            ]]]))
+        );
     }
 
     //
@@ -728,9 +997,11 @@ mod merge_tests {
     //   //
     //
     #[test]
-    fn merge_stitches_together_two_horseshoe_polygons_surrounding_two_other_polygons(
-    ) {
-        println!("merge stitches together two horseshoe polygons surrounding two other polygons");
+    fn merge_stitches_together_two_horseshoe_polygons_surrounding_two_other_polygons()
+     {
+        println!(
+            "merge stitches together two horseshoe polygons surrounding two other polygons"
+        );
 
         let polys = vec![
             topojson::Geometry::new(Value::Polygon(vec![vec![0, 1, 2, 3]])),
@@ -796,4 +1067,305 @@ mod merge_tests {
 
         assert_eq!(merge(&topology, &objects), mp);
     }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn try_merge_reports_malformed_input() {
+        let topology = Topology {
+            arcs: vec![
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                ],
+                vec![vec![0_f64, 0_f64]],
+            ],
+            objects: vec![],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+        let named = |value| NamedGeometry {
+            name: "foo".to_string(),
+            geometry: topojson::Geometry::new(value),
+        };
+
+        assert_eq!(
+            try_merge::<f64>(
+                &topology,
+                &[named(Value::GeometryCollection(vec![
+                    topojson::Geometry::new(Value::Polygon(vec![vec![0]])),
+                    topojson::Geometry::new(Value::LineString(vec![0])),
+                ]))]
+            ),
+            Err(Error::UnexpectedGeometry {
+                expected: "Polygon or MultiPolygon",
+                found: "LineString"
+            })
+        );
+        assert_eq!(
+            try_merge::<f64>(
+                &topology,
+                &[named(Value::Polygon(vec![vec![1]]))]
+            ),
+            Err(Error::EmptyArc { arc: 1 })
+        );
+        assert_eq!(
+            try_merge::<f64>(
+                &topology,
+                &[named(Value::Polygon(vec![vec![2]]))]
+            ),
+            Err(Error::ArcIndexOutOfRange { index: 2, len: 2 })
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn merges_a_topology_which_is_not_quantized() {
+        let file = File::open("./tests/topojson/polygon.json")
+            .expect("Could not load json file.");
+        let topology: Topology =
+            serde_json::from_reader(file).expect("Did not parse correctly.");
+
+        let exterior: LineString<f64> = vec![
+            (0_f64, 0_f64),
+            (0_f64, 10_f64),
+            (10_f64, 10_f64),
+            (10_f64, 0_f64),
+            (0_f64, 0_f64),
+        ]
+        .into();
+        assert_eq!(
+            merge(&topology, &topology.objects),
+            Geometry::MultiPolygon(MultiPolygon(vec![Polygon::new(
+                exterior,
+                vec![]
+            )]))
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    //
+    // The point where the arcs of the exterior meet and the point where the
+    // arcs of the hole meet differ only after the decimal point, the rings
+    // must not be joined.
+    #[test]
+    fn merge_does_not_stitch_together_rings_meeting_at_nearby_points() {
+        let objects = vec![NamedGeometry {
+            name: "a".to_string(),
+            geometry: topojson::Geometry::new(Value::Polygon(vec![
+                vec![0, 1],
+                vec![2, 3],
+            ])),
+        }];
+        let topology = Topology {
+            arcs: vec![
+                vec![
+                    vec![0.2_f64, 0_f64],
+                    vec![0.2_f64, 5_f64],
+                    vec![5_f64, 5_f64],
+                ],
+                vec![
+                    vec![5_f64, 5_f64],
+                    vec![5_f64, 0_f64],
+                    vec![0.2_f64, 0_f64],
+                ],
+                vec![
+                    vec![0.7_f64, 0.4_f64],
+                    vec![0.7_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![1_f64, 0.4_f64],
+                    vec![0.7_f64, 0.4_f64],
+                ],
+            ],
+            objects: objects.clone(),
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            merge_arcs(&topology, &objects),
+            topojson::Geometry::new(Value::MultiPolygon(vec![vec![
+                vec![0, 1],
+                vec![2, 3]
+            ]]))
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn merge_ignores_points_and_lines() {
+        let polygon = NamedGeometry {
+            name: "countries".to_string(),
+            geometry: topojson::Geometry::new(Value::Polygon(vec![vec![0]])),
+        };
+        let mixed = vec![
+            NamedGeometry {
+                name: "countries".to_string(),
+                geometry: topojson::Geometry::new(Value::GeometryCollection(
+                    vec![
+                        topojson::Geometry::new(Value::Polygon(vec![vec![0]])),
+                        topojson::Geometry::new(Value::Point(vec![
+                            0.5_f64, 0.5_f64,
+                        ])),
+                    ],
+                )),
+            },
+            NamedGeometry {
+                name: "rivers".to_string(),
+                geometry: topojson::Geometry::new(Value::MultiLineString(
+                    vec![vec![1]],
+                )),
+            },
+            NamedGeometry {
+                name: "capitals".to_string(),
+                geometry: topojson::Geometry::new(Value::MultiPoint(vec![
+                    vec![0.5_f64, 0.5_f64],
+                ])),
+            },
+        ];
+        let topology = Topology {
+            arcs: vec![
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                    vec![0_f64, 0_f64],
+                ],
+                vec![vec![0_f64, 0_f64], vec![1_f64, 1_f64]],
+            ],
+            objects: mixed.clone(),
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            merge::<f64>(&topology, &mixed),
+            merge::<f64>(&topology, &[polygon])
+        );
+        assert_eq!(
+            try_merge::<f64>(&topology, &mixed),
+            Err(Error::UnexpectedGeometry {
+                expected: "Polygon or MultiPolygon",
+                found: "Point"
+            })
+        );
+        assert_eq!(
+            skipped(&mixed),
+            vec![
+                Skipped {
+                    object: "countries".to_string(),
+                    path: vec![1],
+                    found: "Point"
+                },
+                Skipped {
+                    object: "rivers".to_string(),
+                    path: vec![],
+                    found: "MultiLineString"
+                },
+                Skipped {
+                    object: "capitals".to_string(),
+                    path: vec![],
+                    found: "MultiPoint"
+                },
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn merge_with_provenance_reports_the_source_of_each_polygon() {
+        let county = |id: &str, value| {
+            let mut g = topojson::Geometry::new(value);
+            g.id = Some(json!(id));
+            g
+        };
+        let objects = vec![
+            NamedGeometry {
+                name: "counties".to_string(),
+                geometry: topojson::Geometry::new(Value::GeometryCollection(
+                    vec![
+                        county("a", Value::Polygon(vec![vec![0, 1]])),
+                        county("b", Value::Polygon(vec![vec![-1, 2]])),
+                        county("c", Value::MultiPolygon(vec![vec![vec![3]]])),
+                    ],
+                )),
+            },
+            NamedGeometry {
+                name: "lake".to_string(),
+                geometry: topojson::Geometry::new(Value::Polygon(vec![vec![
+                    4,
+                ]])),
+            },
+        ];
+        let topology = Topology {
+            arcs: vec![
+                vec![vec![1_f64, 1_f64], vec![1_f64, 0_f64]],
+                vec![
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![2_f64, 1_f64],
+                    vec![2_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                ],
+                vec![
+                    vec![3_f64, 0_f64],
+                    vec![3_f64, 1_f64],
+                    vec![4_f64, 1_f64],
+                    vec![4_f64, 0_f64],
+                    vec![3_f64, 0_f64],
+                ],
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                ],
+            ],
+            objects: objects.clone(),
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        let merged = merge_with_provenance::<f64>(&topology, &objects);
+        assert_eq!(
+            merged
+                .iter()
+                .map(|m| {
+                    m.sources
+                        .iter()
+                        .map(|s| {
+                            (s.object.as_str(), s.path.clone(), s.id.clone())
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+            vec![
+                vec![
+                    ("counties", vec![0], Some(json!("a"))),
+                    ("counties", vec![1], Some(json!("b"))),
+                ],
+                vec![("counties", vec![2], Some(json!("c")))],
+                vec![("lake", vec![], None)],
+            ]
+        );
+        assert_eq!(
+            Geometry::MultiPolygon(MultiPolygon(
+                merged.into_iter().map(|m| m.polygon).collect()
+            )),
+            merge(&topology, &objects)
+        );
+    }
 }
@@ -1,57 +1,134 @@
 use std::collections::BTreeMap;
 
-use topojson::{ArcIndexes, Geometry, Topology, Value};
+use geo::CoordFloat;
+use geo::Geometry;
+use geo::MultiLineString;
+use topojson::{ArcIndexes, Topology, Value};
 
+use crate::error::Check;
+use crate::error::Error;
+use crate::feature::feature;
 use crate::stitch::stitch;
 use crate::translate;
 
-fn mesh_arcs(topology: &Topology) -> topojson::Geometry {
-    let n = topology.arcs.len();
+/// A predicate applied to each arc selected by [`mesh`] or [`mesh_arcs`].
+///
+/// The arguments are the first and last geometries which reference the arc.
+/// For an arc used by only one geometry both arguments are the same object,
+/// so `|a, b| !std::ptr::eq(a, b)` selects the interior borders and
+/// `|a, b| std::ptr::eq(a, b)` selects the exterior borders.
+pub type Filter<'a> =
+    &'a dyn Fn(&topojson::Geometry, &topojson::Geometry) -> bool;
 
-    let mut arcs = Vec::with_capacity(n);
-    for (i, a) in arcs.iter_mut().enumerate() {
-        *a = i as i32;
+/// Returns the mesh of the topology as a `geo::MultiLineString`.
+///
+/// When the object is omitted the mesh is built from every arc in the
+/// topology. Otherwise only the arcs referenced by the object are
+/// considered, each arc is emitted once and, if present, the filter decides
+/// which arcs are retained.
+#[must_use]
+pub fn mesh<T>(
+    topology: &Topology,
+    object: Option<&topojson::Geometry>,
+    filter: Option<Filter>,
+) -> MultiLineString<T>
+where
+    T: CoordFloat,
+{
+    let arcs = mesh_arcs(topology, object, filter);
+    match feature(topology, &arcs.value) {
+        Geometry::MultiLineString(mls) => mls,
+        _ => {
+            unreachable!("a MultiLineString must decode to a MultiLineString")
+        }
     }
+}
 
-    topojson::Geometry::new(Value::MultiLineString(stitch(topology, arcs)))
+/// Returns the mesh of the topology as a `geo::MultiLineString`.
+///
+/// The fallible version of [`mesh`].
+///
+/// # Errors
+///
+/// When the arcs considered do not exist or are empty, or when the transform
+/// is malformed.
+pub fn try_mesh<T>(
+    topology: &Topology,
+    object: Option<&topojson::Geometry>,
+    filter: Option<Filter>,
+) -> Result<MultiLineString<T>, Error>
+where
+    T: CoordFloat,
+{
+    let mut check = Check::new(topology, 2)?;
+    match object {
+        Some(object) => check.geometry(&object.value)?,
+        None => check.arcs()?,
+    }
+    Ok(mesh(topology, object, filter))
 }
 
-fn mesh_arcs_with_object_and_filter(
+/// Returns the mesh of the topology as a `TopoJSON` `MultiLineString`.
+///
+/// See [`mesh`], the stitched arcs are returned without being decoded.
+#[must_use]
+pub fn mesh_arcs(
     topology: &Topology,
-    object: &topojson::Geometry,
-    filter: (),
+    object: Option<&topojson::Geometry>,
+    filter: Option<Filter>,
 ) -> topojson::Geometry {
-    let arcs = ExtractArcs::default().generate(topology, object, filter);
-    // topojson::Geometry::new(Value::MultiLineString(stitch(&topology, arcs)))
-    todo!();
+    let arcs = object.map_or_else(
+        || (0_i32..).take(topology.arcs.len()).collect(),
+        |object| ExtractArcs::default().generate(object, filter),
+    );
+
+    topojson::Geometry::new(Value::MultiLineString(stitch(topology, arcs)))
+}
+
+/// An arc index, together with the geometry which referenced it.
+#[derive(Debug)]
+struct ArcUse<'a> {
+    i: i32,
+    g: &'a topojson::Geometry,
 }
-#[derive(Default)]
-struct ExtractArcs {
-    arcs: Vec<ArcIndexes>,
-    geom: Option<Geometry>,
-    geosms_by_arc: BTreeMap<usize, ArcIndexes>,
-    filter: Option<Box<dyn Fn()>>,
+
+#[derive(Debug, Default)]
+struct ExtractArcs<'a> {
+    geom: Option<&'a topojson::Geometry>,
+    geoms_by_arc: BTreeMap<usize, Vec<ArcUse<'a>>>,
 }
 
-impl ExtractArcs {
-    const fn extract0(&self, i: i32) {
+impl<'a> ExtractArcs<'a> {
+    fn extract0(&mut self, i: i32) {
         let j = translate(i);
+        if let Some(g) = self.geom {
+            self.geoms_by_arc
+                .entry(j)
+                .or_default()
+                .push(ArcUse { i, g });
+        }
     }
 
-    fn extract1(&self, arcs: &ArcIndexes) {
-        arcs.iter().for_each(|arc| self.extract0(*arc));
+    fn extract1(&mut self, arcs: &[i32]) {
+        for arc in arcs {
+            self.extract0(*arc);
+        }
     }
 
-    fn extract2(&self, arcs: &[ArcIndexes]) {
-        arcs.iter().for_each(|arc| self.extract1(arc));
+    fn extract2(&mut self, arcs: &[ArcIndexes]) {
+        for arc in arcs {
+            self.extract1(arc);
+        }
     }
 
-    fn extract3(&self, arcs: &[Vec<ArcIndexes>]) {
-        arcs.iter().for_each(|arc| self.extract2(arc));
+    fn extract3(&mut self, arcs: &[Vec<ArcIndexes>]) {
+        for arc in arcs {
+            self.extract2(arc);
+        }
     }
 
-    fn geometry(&mut self, o: &topojson::Geometry) {
-        self.geom = Some(o.clone());
+    fn geometry(&mut self, o: &'a topojson::Geometry) {
+        self.geom = Some(o);
         match &o.value {
             Value::GeometryCollection(gc) => {
                 for g in gc {
@@ -59,31 +136,218 @@ impl ExtractArcs {
                 }
             }
             Value::LineString(arcs) => self.extract1(arcs),
-            Value::MultiLineString(arcs) => self.extract2(arcs),
+            Value::MultiLineString(arcs) | Value::Polygon(arcs) => {
+                self.extract2(arcs);
+            }
             Value::MultiPolygon(arcs) => self.extract3(arcs),
-            _ => {}
+            Value::Point(_) | Value::MultiPoint(_) => {}
         }
     }
 
     fn generate(
         mut self,
-        topology: &Topology,
-        object: &topojson::Geometry,
-        filter: (),
-    ) -> Vec<ArcIndexes> {
+        object: &'a topojson::Geometry,
+        filter: Option<Filter>,
+    ) -> ArcIndexes {
         self.geometry(object);
 
-        self.geosms_by_arc
-            .iter()
-            .for_each(|geoms| match &self.filter {
-                Some(fun) => {
-                    todo!();
-                }
-                None => {
-                    todo!();
+        self.geoms_by_arc
+            .values()
+            .filter_map(|geoms| {
+                let first = geoms.first()?;
+                let last = geoms.last()?;
+                match filter {
+                    Some(filter) if !filter(first.g, last.g) => None,
+                    _ => Some(first.i),
                 }
-            });
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod mesh_tests {
+    use geo::LineString;
+    use geo::MultiLineString;
+    use pretty_assertions::assert_eq;
+    use topojson::Geometry;
+    use topojson::NamedGeometry;
+    use topojson::Topology;
+    use topojson::Value;
+
+    use super::*;
+
+    fn two_line_strings(arcs: Vec<topojson::Arc>) -> Topology {
+        Topology {
+            arcs,
+            objects: vec![NamedGeometry {
+                name: "collection".to_string(),
+                geometry: Geometry::new(Value::GeometryCollection(vec![
+                    Geometry::new(Value::LineString(vec![0])),
+                    Geometry::new(Value::LineString(vec![1])),
+                ])),
+            }],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        }
+    }
+
+    #[test]
+    fn ignores_null_geometries() {
+        println!("mesh ignores null geometries");
+        let topology = Topology {
+            arcs: vec![],
+            objects: vec![],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+        let object = Geometry::new(Value::GeometryCollection(vec![]));
+
+        assert_eq!(
+            mesh::<f64>(&topology, Some(&object), None),
+            MultiLineString(vec![])
+        );
+    }
+
+    #[test]
+    fn stitches_together_two_connected_line_strings() {
+        println!("mesh stitches together two connected line strings");
+        let topology = two_line_strings(vec![
+            vec![vec![1_f64, 0_f64], vec![2_f64, 0_f64]],
+            vec![vec![0_f64, 0_f64], vec![1_f64, 0_f64]],
+        ]);
+        let object = &topology.objects[0].geometry;
+
+        assert_eq!(
+            mesh_arcs(&topology, Some(object), None),
+            Geometry::new(Value::MultiLineString(vec![vec![1, 0]]))
+        );
+        assert_eq!(
+            mesh::<f64>(&topology, Some(object), None),
+            MultiLineString(vec![LineString::from(vec![
+                (0_f64, 0_f64),
+                (1_f64, 0_f64),
+                (2_f64, 0_f64),
+            ])])
+        );
+    }
+
+    #[test]
+    fn does_not_stitch_together_two_disconnected_line_strings() {
+        println!("mesh does not stitch together two disconnected line strings");
+        let topology = two_line_strings(vec![
+            vec![vec![2_f64, 0_f64], vec![3_f64, 0_f64]],
+            vec![vec![0_f64, 0_f64], vec![1_f64, 0_f64]],
+        ]);
+        let object = &topology.objects[0].geometry;
+
+        assert_eq!(
+            mesh_arcs(&topology, Some(object), None),
+            Geometry::new(Value::MultiLineString(vec![vec![0], vec![1]]))
+        );
+        assert_eq!(
+            mesh::<f64>(&topology, Some(object), None),
+            MultiLineString(vec![
+                LineString::from(vec![(2_f64, 0_f64), (3_f64, 0_f64)]),
+                LineString::from(vec![(0_f64, 0_f64), (1_f64, 0_f64)]),
+            ])
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    //
+    // +----+----+
+    // |    |    |
+    // |    |    |
+    // |    |    |
+    // +----+----+
+    //
+    #[test]
+    fn filter_separates_interior_and_exterior_arcs() {
+        let topology = Topology {
+            arcs: vec![
+                vec![vec![1_f64, 1_f64], vec![1_f64, 0_f64]],
+                vec![
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                    vec![0_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![2_f64, 1_f64],
+                    vec![2_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                ],
+            ],
+            objects: vec![NamedGeometry {
+                name: "collection".to_string(),
+                geometry: Geometry::new(Value::GeometryCollection(vec![
+                    Geometry::new(Value::Polygon(vec![vec![0, 1]])),
+                    Geometry::new(Value::Polygon(vec![vec![-1, 2]])),
+                ])),
+            }],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+        let object = &topology.objects[0].geometry;
+
+        let interior = |a: &Geometry, b: &Geometry| !std::ptr::eq(a, b);
+        assert_eq!(
+            mesh_arcs(&topology, Some(object), Some(&interior)),
+            Geometry::new(Value::MultiLineString(vec![vec![0]]))
+        );
+
+        let exterior = |a: &Geometry, b: &Geometry| std::ptr::eq(a, b);
+        assert_eq!(
+            mesh::<f64>(&topology, Some(object), Some(&exterior)),
+            MultiLineString(vec![LineString::from(vec![
+                (1_f64, 0_f64),
+                (0_f64, 0_f64),
+                (0_f64, 1_f64),
+                (1_f64, 1_f64),
+                (2_f64, 1_f64),
+                (2_f64, 0_f64),
+                (1_f64, 0_f64),
+            ])])
+        );
+
+        assert_eq!(
+            mesh_arcs(&topology, None, None),
+            Geometry::new(Value::MultiLineString(vec![vec![0, 1, 2]]))
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn try_mesh_reports_malformed_input() {
+        let topology = two_line_strings(vec![
+            vec![vec![1_f64, 0_f64], vec![2_f64, 0_f64]],
+            vec![vec![0_f64, 0_f64]],
+        ]);
+        let object = &topology.objects[0].geometry;
 
-        self.arcs
+        assert_eq!(
+            try_mesh::<f64>(&topology, Some(object), None),
+            Err(Error::EmptyArc { arc: 1 })
+        );
+        assert_eq!(
+            try_mesh::<f64>(&topology, None, None),
+            Err(Error::EmptyArc { arc: 1 })
+        );
+        assert_eq!(
+            try_mesh::<f64>(
+                &topology,
+                Some(&Geometry::new(Value::LineString(vec![0]))),
+                None
+            ),
+            Ok(MultiLineString(vec![LineString::from(vec![
+                (1_f64, 0_f64),
+                (2_f64, 0_f64),
+            ])]))
+        );
     }
 }
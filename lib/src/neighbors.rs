@@ -1,68 +1,70 @@
-use std::cell::RefCell;
 use std::collections::BTreeMap;
 
-use topojson::{ArcIndexes, NamedGeometry, Value};
+use topojson::{ArcIndexes, Geometry, NamedGeometry, Topology, Value};
 
 use crate::bisect::bisect;
+use crate::error::Check;
+use crate::error::Error;
+use crate::translate;
 
 /// Foreach geometry item produce a list of neigbors.
-pub fn neighbors(objects: &mut [NamedGeometry]) -> Vec<ArcIndexes> {
-    let indexes_by_arc: RefCell<BTreeMap<usize, ArcIndexes>> =
-        RefCell::new(BTreeMap::new());
-    let mut neighbors: Vec<Vec<i32>> = objects.iter().map(|_| vec![]).collect();
-
-    let line = |arcs: &mut ArcIndexes, i: i32| {
-        for a in arcs {
-            let index: usize = if *a < 0 { !*a as usize } else { *a as usize };
-
-            let mut iba = indexes_by_arc.borrow_mut();
-            match iba.get_mut(&index) {
-                Some(o) => o.push(i),
-                None => {
-                    iba.insert(index, vec![i]);
-                }
-            }
-        }
-    };
+///
+/// Two objects are neighbors when they share an arc. Points and multi-points
+/// have no neighbors.
+#[must_use]
+pub fn neighbors(objects: &[NamedGeometry]) -> Vec<ArcIndexes> {
+    neighbors_of(objects.iter().map(|o| &o.geometry))
+}
 
-    let polygon = |arcs: &mut Vec<ArcIndexes>, i: i32| {
-        for arc in arcs {
-            line(arc, i);
-        }
-    };
+/// Foreach geometry item produce a list of neigbors.
+///
+/// The fallible version of [`neighbors`], the objects are checked against
+/// the topology they belong to.
+///
+/// # Errors
+///
+/// When an object references arcs which do not exist, or when a line or a
+/// position is empty.
+pub fn try_neighbors(
+    topology: &Topology,
+    objects: &[NamedGeometry],
+) -> Result<Vec<ArcIndexes>, Error> {
+    let mut check = Check::new(topology, 0)?;
+    for o in objects {
+        check.geometry(&o.geometry.value)?;
+    }
+    Ok(neighbors(objects))
+}
 
-    let named_geometry = |o: &mut NamedGeometry, i: i32| {
-        match &mut o.geometry.value {
-            Value::GeometryCollection(gc) => {
-                for _elem in gc {
-                    todo!();
-                }
-            }
-            Value::LineString(l) => {
-                println!("calling from i={i}");
-                line(l, i);
-            }
-            Value::MultiLineString(p) | Value::Polygon(p) => polygon(p, i),
-            Value::MultiPolygon(_mp) => {
-                todo!()
-                // function (arcs, i) { arcs.forEach(function (arc) { polygon(arc, i); }); }
-            }
-            _ => {
-                todo!("What is mising here!");
-            }
-        }
-    };
+/// Foreach member of a geometry collection produce a list of neigbors.
+///
+/// See [`neighbors`].
+#[must_use]
+pub fn geometry_neighbors(geometries: &[Geometry]) -> Vec<ArcIndexes> {
+    neighbors_of(geometries.iter())
+}
+
+fn neighbors_of<'a>(
+    geometries: impl ExactSizeIterator<Item = &'a Geometry>,
+) -> Vec<ArcIndexes> {
+    let mut indexes_by_arc: BTreeMap<usize, ArcIndexes> = BTreeMap::new();
+    let mut neighbors: Vec<ArcIndexes> = vec![vec![]; geometries.len()];
 
-    for (i, o) in objects.iter_mut().enumerate() {
-        named_geometry(o, i as i32);
+    for (i, o) in (0_i32..).zip(geometries) {
+        geometry(&mut indexes_by_arc, o, i);
     }
 
-    for indexes_i in indexes_by_arc.borrow().values() {
+    for indexes_i in indexes_by_arc.values() {
         let m = indexes_i.len();
         for j in 0..m {
             for k in j + 1..m {
                 let ij = indexes_i[j];
                 let ik = indexes_i[k];
+                // Members of a collection may share arcs with each other,
+                // an object is not its own neighbor.
+                if ij == ik {
+                    continue;
+                }
 
                 let n = &mut neighbors[ij as usize];
                 let b = bisect(n, ik);
@@ -95,18 +97,61 @@ pub fn neighbors(objects: &mut [NamedGeometry]) -> Vec<ArcIndexes> {
     neighbors
 }
 
+fn line(
+    indexes_by_arc: &mut BTreeMap<usize, ArcIndexes>,
+    arcs: &[i32],
+    i: i32,
+) {
+    for a in arcs {
+        indexes_by_arc.entry(translate(*a)).or_default().push(i);
+    }
+}
+
+fn polygon(
+    indexes_by_arc: &mut BTreeMap<usize, ArcIndexes>,
+    arcs: &[ArcIndexes],
+    i: i32,
+) {
+    for arc in arcs {
+        line(indexes_by_arc, arc, i);
+    }
+}
+
+fn geometry(
+    indexes_by_arc: &mut BTreeMap<usize, ArcIndexes>,
+    o: &Geometry,
+    i: i32,
+) {
+    match &o.value {
+        Value::GeometryCollection(gc) => {
+            for g in gc {
+                geometry(indexes_by_arc, g, i);
+            }
+        }
+        Value::LineString(l) => line(indexes_by_arc, l, i),
+        Value::MultiLineString(p) | Value::Polygon(p) => {
+            polygon(indexes_by_arc, p, i);
+        }
+        Value::MultiPolygon(mp) => {
+            for p in mp {
+                polygon(indexes_by_arc, p, i);
+            }
+        }
+        Value::Point(_) | Value::MultiPoint(_) => {}
+    }
+}
+
 #[cfg(test)]
 mod neighbors_tests {
 
     use super::*;
     use pretty_assertions::assert_eq;
-    use topojson::Geometry;
     use topojson::Topology;
 
     #[test]
     fn empty_array_empty_input() {
         println!("neighbors returns an empty array for empty input");
-        assert_eq!(neighbors(&mut []).len(), 0);
+        assert_eq!(neighbors(&[]).len(), 0);
     }
 
     //
@@ -120,7 +165,7 @@ mod neighbors_tests {
             "neighbors returns an empty array for objects with no neighbors"
         );
 
-        let mut topology = Topology {
+        let topology = Topology {
             arcs: vec![
                 vec![vec![0_f64, 0_f64], vec![1_f64, 0_f64]],
                 vec![vec![0_f64, 1_f64], vec![1_f64, 1_f64]],
@@ -141,7 +186,7 @@ mod neighbors_tests {
             foreign_members: None,
         };
 
-        let n = neighbors(&mut topology.objects);
+        let n = neighbors(&topology.objects);
         let expected: Vec<ArcIndexes> = vec![vec![], vec![]];
         assert_eq!(n, expected);
     }
@@ -154,7 +199,7 @@ mod neighbors_tests {
         println!(
             "neighbors geometries that only share isolated points are not considered neighbors"
         );
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "ab".to_string(),
@@ -175,7 +220,7 @@ mod neighbors_tests {
         };
 
         let expected: Vec<ArcIndexes> = vec![vec![], vec![]];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
     }
 
     //
@@ -186,7 +231,7 @@ mod neighbors_tests {
         println!(
             "neighbors geometries that share arcs are considered neighbors"
         );
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "abc".to_string(),
@@ -208,7 +253,7 @@ mod neighbors_tests {
         };
 
         let expected: Vec<ArcIndexes> = vec![vec![1], vec![0]];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
     }
 
     //
@@ -220,7 +265,7 @@ mod neighbors_tests {
         println!(
             "neighbors geometries that share arcs are considered neighbors"
         );
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "abc".to_string(),
@@ -242,7 +287,7 @@ mod neighbors_tests {
         };
 
         let expected: Vec<ArcIndexes> = vec![vec![1], vec![0]];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
     }
 
     //
@@ -251,7 +296,7 @@ mod neighbors_tests {
     #[test]
     fn neighbors_are_returned_in_sorted_order_by_index() {
         println!("neighbors neighbors are returned in sorted order by index");
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "abcd".to_string(),
@@ -304,7 +349,7 @@ mod neighbors_tests {
             vec![0, 1, 2, 3, 5],
             vec![0, 1, 2, 3, 4],
         ];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
     }
 
     // //
@@ -321,7 +366,7 @@ mod neighbors_tests {
         println!(
             "neighbors the polygons ABCDA and BEFCB are neighbors, but GHIG is not"
         );
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "abcda".to_string(),
@@ -362,7 +407,7 @@ mod neighbors_tests {
             foreign_members: None,
         };
         let expected: Vec<ArcIndexes> = vec![vec![1], vec![0], vec![]];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
     }
 
     // //
@@ -383,7 +428,7 @@ mod neighbors_tests {
         println!(
             "neighbors the polygons ABEDGHKJA and BCLKHIFEB are neighbors, and not listed twice"
         );
-        let mut topology = Topology {
+        let topology = Topology {
             objects: vec![
                 NamedGeometry {
                     name: "abdeghkja".to_string(),
@@ -431,6 +476,96 @@ mod neighbors_tests {
             foreign_members: None,
         };
         let expected: Vec<ArcIndexes> = vec![vec![1], vec![0]];
-        assert_eq!(neighbors(&mut topology.objects), expected);
+        assert_eq!(neighbors(&topology.objects), expected);
+    }
+
+    // There is no equivalent test in the javascript version.
+    //
+    // A-----B-----C     G
+    // |     |     |
+    // |     |     |
+    // D-----E-----F
+    //
+    #[test]
+    fn geometry_collections_and_multipolygons_are_supported() {
+        let topology = Topology {
+            objects: vec![
+                NamedGeometry {
+                    name: "collection".to_string(),
+                    geometry: Geometry::new(Value::GeometryCollection(vec![
+                        Geometry::new(Value::Polygon(vec![vec![0, 1]])),
+                        Geometry::new(Value::Point(vec![6_f64, 1_f64])),
+                    ])),
+                },
+                NamedGeometry {
+                    name: "multipolygon".to_string(),
+                    geometry: Geometry::new(Value::MultiPolygon(vec![vec![
+                        vec![2, -1],
+                    ]])),
+                },
+                NamedGeometry {
+                    name: "g".to_string(),
+                    geometry: Geometry::new(Value::MultiPoint(vec![vec![
+                        6_f64, 1_f64,
+                    ]])),
+                },
+            ],
+            arcs: vec![
+                vec![vec![1_f64, 0_f64], vec![1_f64, 1_f64]],
+                vec![
+                    vec![1_f64, 1_f64],
+                    vec![0_f64, 1_f64],
+                    vec![0_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                ],
+                vec![
+                    vec![1_f64, 0_f64],
+                    vec![2_f64, 0_f64],
+                    vec![2_f64, 1_f64],
+                    vec![1_f64, 1_f64],
+                ],
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        let expected: Vec<ArcIndexes> = vec![vec![1], vec![0], vec![]];
+        assert_eq!(neighbors(&topology.objects), expected);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn members_of_a_collection_are_not_their_own_neighbors() {
+        let geometries = vec![
+            Geometry::new(Value::GeometryCollection(vec![
+                Geometry::new(Value::Polygon(vec![vec![0, 1]])),
+                Geometry::new(Value::Polygon(vec![vec![2, -1]])),
+            ])),
+            Geometry::new(Value::LineString(vec![2])),
+        ];
+
+        let expected: Vec<ArcIndexes> = vec![vec![1], vec![0]];
+        assert_eq!(geometry_neighbors(&geometries), expected);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn try_neighbors_reports_out_of_range_arcs() {
+        let topology = Topology {
+            objects: vec![NamedGeometry {
+                name: "ab".to_string(),
+                geometry: Geometry::new(Value::LineString(vec![0, -2])),
+            }],
+            arcs: vec![vec![vec![0_f64, 0_f64], vec![1_f64, 0_f64]]],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            try_neighbors(&topology, &topology.objects),
+            Err(Error::ArcIndexOutOfRange { index: -2, len: 1 })
+        );
     }
 }
@@ -7,6 +7,8 @@ use topojson::ArcIndexes;
 #[derive(Clone, Debug)]
 pub struct PolygonU {
     pub v: Vec<ArcIndexes>,
+    /// Identifies the input the polygon was taken from.
+    pub source: usize,
     underscore: bool,
 }
 
@@ -14,6 +16,7 @@ impl From<Vec<ArcIndexes>> for PolygonU {
     fn from(v: Vec<ArcIndexes>) -> Self {
         Self {
             v,
+            source: 0,
             underscore: false,
         }
     }
@@ -25,6 +28,7 @@ impl PolygonU {
     pub(super) const fn new(v: Vec<ArcIndexes>) -> Self {
         Self {
             v,
+            source: 0,
             underscore: false,
         }
     }
@@ -0,0 +1,178 @@
+use topojson::{ArcIndexes, Topology, Value};
+
+use crate::translate;
+
+/// Removes the arcs which are not referenced by any object, and renumbers
+/// the arc indexes of every geometry to match.
+///
+/// Use it after removing objects to shrink the topology. The order of the
+/// remaining arcs is preserved, and a reversed arc is still referenced by
+/// the ones' complement of its new index. Indexes beyond the end of the arcs
+/// are left unchanged, and so remain out of range.
+pub fn prune(topology: &mut Topology) {
+    let mut used = vec![false; topology.arcs.len()];
+    for o in &mut topology.objects {
+        arc_indexes(&mut o.geometry.value, &mut |arcs| {
+            for arc in arcs.iter() {
+                if let Some(u) = used.get_mut(translate(*arc)) {
+                    *u = true;
+                }
+            }
+        });
+    }
+
+    // The new index of each arc which is used.
+    let mut new_index = Vec::with_capacity(used.len());
+    let mut n = 0_usize;
+    for u in &used {
+        new_index.push(n);
+        if *u {
+            n += 1;
+        }
+    }
+
+    let mut used_iter = used.iter();
+    topology
+        .arcs
+        .retain(|_| *used_iter.next().unwrap_or(&false));
+
+    for o in &mut topology.objects {
+        arc_indexes(&mut o.geometry.value, &mut |arcs| {
+            for arc in arcs.iter_mut() {
+                if let Some(&i) = new_index.get(translate(*arc)) {
+                    // The new index is never greater than the old one.
+                    #[allow(clippy::cast_possible_truncation)]
+                    #[allow(clippy::cast_possible_wrap)]
+                    let i = i as i32;
+                    *arc = if *arc < 0 { !i } else { i };
+                }
+            }
+        });
+    }
+}
+
+/// Calls `f` with each line and ring of the geometry.
+fn arc_indexes<F>(value: &mut Value, f: &mut F)
+where
+    F: FnMut(&mut ArcIndexes),
+{
+    match value {
+        Value::GeometryCollection(gc) => {
+            for g in gc {
+                arc_indexes(&mut g.value, f);
+            }
+        }
+        Value::LineString(arcs) => f(arcs),
+        Value::MultiLineString(lines) | Value::Polygon(lines) => {
+            lines.iter_mut().for_each(f);
+        }
+        Value::MultiPolygon(polygons) => {
+            for rings in polygons {
+                rings.iter_mut().for_each(&mut *f);
+            }
+        }
+        Value::Point(_) | Value::MultiPoint(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod prune_tests {
+    use geo::{line_string, polygon};
+    use pretty_assertions::assert_eq;
+    use topojson::NamedGeometry;
+
+    use super::*;
+    use crate::feature::feature_from_name;
+    use crate::topology::topology;
+
+    fn arc(x: f64) -> Vec<Vec<f64>> {
+        vec![vec![x, 0_f64], vec![x, 1_f64]]
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn removes_unreferenced_arcs() {
+        let mut topology = Topology {
+            arcs: (0..6).map(|i| arc(f64::from(i))).collect(),
+            objects: vec![
+                NamedGeometry {
+                    name: "point".to_string(),
+                    geometry: topojson::Geometry::new(Value::Point(vec![
+                        0_f64, 0_f64,
+                    ])),
+                },
+                NamedGeometry {
+                    name: "collection".to_string(),
+                    geometry: topojson::Geometry::new(
+                        Value::GeometryCollection(vec![
+                            topojson::Geometry::new(Value::LineString(vec![
+                                1, -4,
+                            ])),
+                            topojson::Geometry::new(Value::MultiPolygon(vec![
+                                vec![vec![-6], vec![3]],
+                            ])),
+                        ]),
+                    ),
+                },
+                NamedGeometry {
+                    name: "broken".to_string(),
+                    geometry: topojson::Geometry::new(Value::MultiLineString(
+                        vec![vec![-2], vec![9]],
+                    )),
+                },
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+        prune(&mut topology);
+
+        // Arcs 0, 2 and 4 are unused.
+        assert_eq!(topology.arcs, vec![arc(1.), arc(3.), arc(5.)]);
+        assert_eq!(
+            topology.objects[0].geometry.value,
+            Value::Point(vec![0_f64, 0_f64])
+        );
+        assert_eq!(
+            topology.objects[1].geometry.value,
+            Value::GeometryCollection(vec![
+                topojson::Geometry::new(Value::LineString(vec![0, -2])),
+                topojson::Geometry::new(Value::MultiPolygon(vec![vec![
+                    vec![-3],
+                    vec![1]
+                ]])),
+            ])
+        );
+        assert_eq!(
+            topology.objects[2].geometry.value,
+            Value::MultiLineString(vec![vec![-1], vec![9]])
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn extracts_a_subset() {
+        let mut t = topology(
+            vec![
+                (
+                    "river".to_string(),
+                    line_string![(x: 0., y: 0.), (x: 5., y: 5.)].into(),
+                ),
+                (
+                    "lake".to_string(),
+                    polygon![(x: 6., y: 6.), (x: 7., y: 6.), (x: 7., y: 7.)]
+                        .into(),
+                ),
+            ],
+            Some(100),
+        );
+        let lake = feature_from_name::<f64>(&t, "lake");
+
+        t.objects.retain(|o| o.name == "lake");
+        prune(&mut t);
+
+        assert_eq!(t.arcs.len(), 1);
+        assert_eq!(t.objects[0].geometry.value, Value::Polygon(vec![vec![0]]));
+        assert_eq!(feature_from_name::<f64>(&t, "lake"), lake);
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+use topojson::{Arc, NamedGeometry, Topology, TransformParams, Value};
+
+use crate::bbox::bbox;
+use crate::untransform::Untransform;
+
+/// Reasons why a topology cannot be quantized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuantizeError {
+    /// The topology already has a transform.
+    AlreadyQuantized,
+    /// The quantization parameter must be at least two.
+    InvalidQuantization(u32),
+}
+
+impl fmt::Display for QuantizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AlreadyQuantized => write!(f, "already quantized"),
+            Self::InvalidQuantization(n) => write!(f, "n must be ≥2, got {n}"),
+        }
+    }
+}
+
+impl std::error::Error for QuantizeError {}
+
+/// Returns a quantized copy of the topology.
+///
+/// The transform is computed from the bbox of the topology, such that each
+/// axis is divided into `n` steps. If the topology has no bbox, one is
+/// computed. Arcs are delta-encoded and coincident consecutive points are
+/// removed, although each arc retains at least two points. Point and
+/// `MultiPoint` geometries are quantized, other geometries are copied.
+///
+/// # Errors
+///
+/// When the topology is already quantized or when `n` is less than two.
+pub fn quantize(
+    topology: &Topology,
+    n: u32,
+) -> Result<Topology, QuantizeError> {
+    if topology.transform.is_some() {
+        return Err(QuantizeError::AlreadyQuantized);
+    }
+    if n < 2 {
+        return Err(QuantizeError::InvalidQuantization(n));
+    }
+
+    let bbox = match &topology.bbox {
+        Some(b) if b.len() >= 4 => Some([b[0], b[1], b[2], b[3]]),
+        _ => bbox(topology),
+    };
+
+    let transform = bbox.map_or(
+        TransformParams {
+            scale: [1_f64, 1_f64],
+            translate: [0_f64, 0_f64],
+        },
+        |[x0, y0, x1, y1]| {
+            let steps = f64::from(n - 1);
+            TransformParams {
+                scale: [
+                    if x1 - x0 == 0_f64 {
+                        1_f64
+                    } else {
+                        (x1 - x0) / steps
+                    },
+                    if y1 - y0 == 0_f64 {
+                        1_f64
+                    } else {
+                        (y1 - y0) / steps
+                    },
+                ],
+                translate: [x0, y0],
+            }
+        },
+    );
+
+    let t = Untransform::new(Some(&transform));
+
+    let objects = topology
+        .objects
+        .iter()
+        .map(|o| NamedGeometry {
+            name: o.name.clone(),
+            geometry: quantize_geometry(&t, &o.geometry),
+        })
+        .collect();
+
+    let arcs = topology
+        .arcs
+        .iter()
+        .map(|arc| quantize_arc(&t, arc))
+        .collect();
+
+    Ok(Topology {
+        bbox: topology.bbox.clone().or_else(|| bbox.map(Vec::from)),
+        objects,
+        transform: Some(transform),
+        arcs,
+        foreign_members: topology.foreign_members.clone(),
+    })
+}
+
+fn quantize_geometry(
+    t: &Untransform,
+    input: &topojson::Geometry,
+) -> topojson::Geometry {
+    let value = match &input.value {
+        Value::GeometryCollection(gc) => Value::GeometryCollection(
+            gc.iter().map(|g| quantize_geometry(t, g)).collect(),
+        ),
+        Value::Point(p) => Value::Point(quantize_point(t, p)),
+        Value::MultiPoint(mp) => {
+            Value::MultiPoint(mp.iter().map(|p| quantize_point(t, p)).collect())
+        }
+        _ => return input.clone(),
+    };
+
+    topojson::Geometry {
+        bbox: input.bbox.clone(),
+        value,
+        properties: input.properties.clone(),
+        id: input.id.clone(),
+        foreign_members: input.foreign_members.clone(),
+    }
+}
+
+/// Returns the absolute quantized position, extra dimensions are copied
+/// over untouched.
+fn quantize_point(t: &Untransform, p: &[f64]) -> Vec<f64> {
+    let mut output = Vec::with_capacity(p.len());
+    t.encoder().encode_into(p, &mut output);
+    output
+}
+
+fn quantize_arc(t: &Untransform, input: &Arc) -> Arc {
+    let mut output: Arc = Vec::with_capacity(input.len());
+    let mut encoder = t.encoder();
+    for (i, position) in input.iter().enumerate() {
+        let mut p = Vec::with_capacity(position.len());
+        encoder.encode_into(position, &mut p);
+        // Remove coincident points.
+        if i == 0 || p[0] != 0_f64 || p[1] != 0_f64 {
+            output.push(p);
+        }
+    }
+
+    // An arc must have at least two points.
+    if output.len() == 1 {
+        output.push(vec![0_f64, 0_f64]);
+    }
+    output
+}
+
+#[cfg(test)]
+mod quantize_tests {
+    use std::fs::File;
+
+    use pretty_assertions::assert_eq;
+    use topojson::Topology;
+
+    use super::*;
+
+    fn read(path: &str) -> Topology {
+        let file = File::open(path).expect("Could not load json file.");
+        serde_json::from_reader(file).expect("Did not parse correctly.")
+    }
+
+    #[test]
+    fn quantizes_the_input_topology() {
+        println!("topojson.quantize(topology, n) quantizes the input topology");
+        assert_eq!(
+            quantize(&read("./tests/topojson/polygon.json"), 10_000),
+            Ok(read("./tests/topojson/polygon-q1e4.json"))
+        );
+        assert_eq!(
+            quantize(&read("./tests/topojson/polygon-mercator.json"), 100_000),
+            Ok(read("./tests/topojson/polygon-mercator-q1e5.json"))
+        );
+    }
+
+    #[test]
+    fn ensures_that_each_arc_has_at_least_two_points() {
+        println!(
+            "topojson.quantize(topology, n) ensures that each arc has at least two points"
+        );
+        assert_eq!(
+            quantize(&read("./tests/topojson/empty.json"), 10_000),
+            Ok(read("./tests/topojson/empty-q1e4.json"))
+        );
+    }
+
+    #[test]
+    fn preserves_the_id_bbox_and_properties_of_input_objects() {
+        println!(
+            "topojson.quantize(topology, n) preserves the id, bbox and properties of input objects"
+        );
+        assert_eq!(
+            quantize(&read("./tests/topojson/properties.json"), 10_000),
+            Ok(read("./tests/topojson/properties-q1e4.json"))
+        );
+    }
+
+    #[test]
+    fn throws_an_error_if_n_is_not_at_least_two() {
+        println!(
+            "topojson.quantize(topology, n) throws an error if n is not at least two"
+        );
+        let topology = read("./tests/topojson/polygon.json");
+        assert_eq!(
+            quantize(&topology, 0),
+            Err(QuantizeError::InvalidQuantization(0))
+        );
+        assert_eq!(
+            quantize(&topology, 1),
+            Err(QuantizeError::InvalidQuantization(1))
+        );
+    }
+
+    #[test]
+    fn throws_an_error_if_the_topology_is_already_quantized() {
+        println!(
+            "topojson.quantize(topology, n) throws an error if the topology is already quantized"
+        );
+        let topology = read("./tests/topojson/polygon-q1e4.json");
+        assert_eq!(
+            quantize(&topology, 10_000),
+            Err(QuantizeError::AlreadyQuantized)
+        );
+    }
+
+    #[test]
+    fn quantizes_points() {
+        println!("topojson.quantize(topology, n) quantizes points");
+        assert_eq!(
+            quantize(&read("./tests/topojson/point.json"), 100_000),
+            Ok(read("./tests/topojson/point-q1e5.json"))
+        );
+        assert_eq!(
+            quantize(&read("./tests/topojson/points.json"), 100_000),
+            Ok(read("./tests/topojson/points-q1e5.json"))
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn converts_into_the_crate_error() {
+        fn quantized(topology: &Topology) -> Result<Topology, crate::Error> {
+            Ok(quantize(topology, 10_000)?)
+        }
+        let topology = read("./tests/topojson/polygon-q1e4.json");
+        assert_eq!(
+            quantized(&topology),
+            Err(crate::Error::Quantize(QuantizeError::AlreadyQuantized))
+        );
+    }
+}
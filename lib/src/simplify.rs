@@ -0,0 +1,572 @@
+use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::f64::consts::{FRAC_PI_4, TAU};
+
+use topojson::{Arc, NamedGeometry, Topology};
+
+use crate::decoded::decode_geometry;
+use crate::transform::Transform;
+
+/// Returns the area of the triangle, in the plane.
+///
+/// A weight for [`presimplify`], suited to projected topologies.
+#[must_use]
+// `mul_add()` rounds differently from javascript.
+#[allow(clippy::suboptimal_flops)]
+pub fn planar_triangle_area(triangle: &[[f64; 2]; 3]) -> f64 {
+    let [a, b, c] = triangle;
+    ((a[0] - c[0]) * (b[1] - a[1]) - (a[0] - b[0]) * (c[1] - a[1])).abs()
+        / 2_f64
+}
+
+/// Returns the area of the ring, in the plane.
+///
+/// The ring may be open or closed.
+#[must_use]
+// `mul_add()` rounds differently from javascript.
+#[allow(clippy::suboptimal_flops)]
+pub fn planar_ring_area(ring: &[[f64; 2]]) -> f64 {
+    let Some(mut b) = ring.last() else {
+        return 0_f64;
+    };
+    let mut area = 0_f64;
+    for a in ring {
+        area += b[0] * a[1] - b[1] * a[0];
+        b = a;
+    }
+    area.abs() / 2_f64
+}
+
+/// Returns twice the signed spherical area between the edges of the ring
+/// and the south pole.
+///
+/// Positions are longitude and latitude in degrees. For a closed ring the
+/// first position is skipped as an edge start, otherwise the ring is closed
+/// from its last position.
+// `mul_add()` rounds differently from javascript.
+#[allow(clippy::suboptimal_flops)]
+fn half_area(ring: &[[f64; 2]], closed: bool) -> f64 {
+    let Some(last) = ring.last() else {
+        return 0_f64;
+    };
+    let mut positions = ring.iter();
+    let start = if closed {
+        positions.next().unwrap_or(last)
+    } else {
+        last
+    };
+
+    let mut lambda1 = start[0].to_radians();
+    let phi1 = start[1].to_radians() / 2_f64 + FRAC_PI_4;
+    let (mut sin_phi1, mut cos_phi1) = phi1.sin_cos();
+
+    let mut sum = 0_f64;
+    for p in positions {
+        let lambda0 = lambda1;
+        lambda1 = p[0].to_radians();
+        let phi1 = p[1].to_radians() / 2_f64 + FRAC_PI_4;
+        let (sin_phi0, cos_phi0) = (sin_phi1, cos_phi1);
+        (sin_phi1, cos_phi1) = phi1.sin_cos();
+
+        // Spherical excess E for a spherical triangle with vertices: south
+        // pole, previous point, current point. Uses a formula derived from
+        // Cagnoli’s theorem. See Todhunter, Spherical Trig. (1871), Sec.
+        // 103, Eq. (2).
+        let d_lambda = lambda1 - lambda0;
+        let sd_lambda = if d_lambda >= 0_f64 { 1_f64 } else { -1_f64 };
+        let ad_lambda = sd_lambda * d_lambda;
+        let k = sin_phi0 * sin_phi1;
+        let u = cos_phi0 * cos_phi1 + k * ad_lambda.cos();
+        let v = k * sd_lambda * ad_lambda.sin();
+        sum += v.atan2(u);
+    }
+    sum
+}
+
+/// Returns the area of the ring on the unit sphere, in steradians.
+///
+/// Positions are longitude and latitude in degrees, and the ring must be
+/// closed. Exterior rings are expected to be clockwise, as in `TopoJSON`,
+/// holes anticlockwise; pass `interior` for holes.
+#[must_use]
+pub fn spherical_ring_area(ring: &[[f64; 2]], interior: bool) -> f64 {
+    let mut sum = half_area(ring, true);
+    if interior {
+        sum *= -1_f64;
+    }
+    (if sum < 0_f64 { TAU + sum } else { sum }) * 2_f64
+}
+
+/// Returns the area of the triangle on the unit sphere, in steradians.
+///
+/// A weight for [`presimplify`], suited to topologies whose positions are
+/// longitude and latitude in degrees, such as the world atlas files. Unlike
+/// [`planar_triangle_area`] it does not inflate areas away from the equator.
+#[must_use]
+pub fn spherical_triangle_area(triangle: &[[f64; 2]; 3]) -> f64 {
+    half_area(triangle, false).abs() * 2_f64
+}
+
+/// Returns a copy of the topology in which the third dimension of every
+/// arc position holds its Visvalingam weight.
+///
+/// The weight of a position is the weight of the triangle it forms with its
+/// neighbours, as computed by the weight function, once all positions of
+/// lower weight have been removed. It never decreases along the elimination
+/// order, so removing every position below a threshold never removes a
+/// position before one of lower weight. The first and last positions of
+/// each arc have an infinite weight, they are always kept, so borders stay
+/// identical for the polygons on either side.
+///
+/// The returned topology is untransformed, any extra dimensions are
+/// dropped, and the positions of points are decoded. Use [`simplify`] to
+/// remove positions.
+#[must_use]
+pub fn presimplify<F>(topology: &Topology, weight: F) -> Topology
+where
+    F: Fn(&[[f64; 2]; 3]) -> f64,
+{
+    let t = Transform::new(topology.transform.as_ref());
+
+    let arcs = topology
+        .arcs
+        .iter()
+        .map(|arc| -> Arc {
+            let mut d = t.decoder();
+            let points: Vec<[f64; 2]> =
+                arc.iter().map(|p| d.decode(p)).collect();
+            let weights = weights(&points, &weight);
+            points
+                .iter()
+                .zip(weights)
+                .map(|(p, w)| vec![p[0], p[1], w])
+                .collect()
+        })
+        .collect();
+
+    let objects = topology
+        .objects
+        .iter()
+        .map(|o| NamedGeometry {
+            name: o.name.clone(),
+            geometry: decode_geometry(&t, &o.geometry),
+        })
+        .collect();
+
+    Topology {
+        bbox: topology.bbox.clone(),
+        objects,
+        transform: None,
+        arcs,
+        foreign_members: topology.foreign_members.clone(),
+    }
+}
+
+/// A triangle in the heap, identified by the index of its middle position.
+///
+/// `version` identifies the latest weight of the triangle, older entries
+/// are skipped when popped.
+#[derive(Debug, PartialEq)]
+struct Entry {
+    weight: f64,
+    i: usize,
+    version: usize,
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight
+            .total_cmp(&other.weight)
+            .then(self.i.cmp(&other.i))
+    }
+}
+
+/// Computes the effective weight of each position of an arc.
+fn weights<F>(points: &[[f64; 2]], weight: &F) -> Vec<f64>
+where
+    F: Fn(&[[f64; 2]; 3]) -> f64,
+{
+    let n = points.len();
+    // Always keep the arc endpoints!
+    let mut weights = vec![f64::INFINITY; n];
+    if n < 3 {
+        return weights;
+    }
+
+    // The positions which remain either side of each position.
+    let mut previous: Vec<usize> =
+        (0..n).map(|i| i.saturating_sub(1)).collect();
+    let mut next: Vec<usize> = (1..=n).collect();
+    let mut version = vec![0_usize; n];
+
+    let triangle = |previous: usize, i: usize, next: usize| {
+        weight(&[points[previous], points[i], points[next]])
+    };
+
+    let mut heap = BinaryHeap::with_capacity(n - 2);
+    for (i, w) in weights.iter_mut().enumerate().take(n - 1).skip(1) {
+        *w = triangle(i - 1, i, i + 1);
+        heap.push(Reverse(Entry {
+            weight: *w,
+            i,
+            version: 0,
+        }));
+    }
+
+    let mut max_weight = 0_f64;
+    while let Some(Reverse(entry)) = heap.pop() {
+        let i = entry.i;
+        if entry.version != version[i] {
+            continue;
+        }
+        // Marks the position as removed.
+        version[i] = usize::MAX;
+
+        // If the weight of the current point is less than that of the
+        // previous point to be eliminated, use the latter’s weight instead.
+        // This ensures that the current point cannot be eliminated without
+        // eliminating previously-eliminated points.
+        if weights[i] < max_weight {
+            weights[i] = max_weight;
+        } else {
+            max_weight = weights[i];
+        }
+
+        let (p, q) = (previous[i], next[i]);
+        next[p] = q;
+        previous[q] = p;
+        for j in [p, q] {
+            if j != 0 && j != n - 1 {
+                weights[j] = triangle(previous[j], j, next[j]);
+                version[j] += 1;
+                heap.push(Reverse(Entry {
+                    weight: weights[j],
+                    i: j,
+                    version: version[j],
+                }));
+            }
+        }
+    }
+
+    weights
+}
+
+/// Returns a copy of the presimplified topology, keeping only the positions
+/// whose weight is at least `min_weight`.
+///
+/// Positions without a weight are removed, so the topology must first be
+/// passed through [`presimplify`]. The weights are dropped.
+/// `f64::MIN_POSITIVE` removes only the positions of zero weight, which do
+/// not change the shape of the arcs.
+#[must_use]
+pub fn simplify(topology: &Topology, min_weight: f64) -> Topology {
+    let arcs = topology
+        .arcs
+        .iter()
+        .map(|arc| -> Arc {
+            arc.iter()
+                .filter(|p| p.get(2).is_some_and(|w| *w >= min_weight))
+                .map(|p| vec![p[0], p[1]])
+                .collect()
+        })
+        .collect();
+
+    Topology {
+        bbox: topology.bbox.clone(),
+        objects: topology.objects.clone(),
+        transform: topology.transform.clone(),
+        arcs,
+        foreign_members: topology.foreign_members.clone(),
+    }
+}
+
+/// Returns the weight which [`simplify`] should be given to keep the
+/// fraction `p` of the positions of a presimplified topology.
+///
+/// The arc endpoints, which are always kept, are not counted. Returns zero
+/// when there are no other positions.
+#[must_use]
+// `mul_add()` rounds differently from javascript.
+#[allow(clippy::suboptimal_flops)]
+pub fn quantile(topology: &Topology, p: f64) -> f64 {
+    let mut weights: Vec<f64> = topology
+        .arcs
+        .iter()
+        .flatten()
+        .filter_map(|position| position.get(2).copied())
+        .filter(|w| w.is_finite())
+        .collect();
+    if weights.is_empty() {
+        return 0_f64;
+    }
+    weights.sort_unstable_by(|a, b| b.total_cmp(a));
+
+    // As d3.quantile().
+    let n = weights.len();
+    if p <= 0_f64 || n < 2 {
+        return weights[0];
+    }
+    if p >= 1_f64 {
+        return weights[n - 1];
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let i = (n - 1) as f64 * p;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let i0 = i.floor() as usize;
+    let value0 = weights[i0];
+    let value1 = weights[i0 + 1];
+    value0 + (value1 - value0) * i.fract()
+}
+
+#[cfg(test)]
+mod simplify_tests {
+    use std::f64::consts::PI;
+    use std::fs::File;
+
+    use pretty_assertions::assert_eq;
+    use topojson::TransformParams;
+    use topojson::Value;
+
+    use super::*;
+    use crate::feature::feature;
+    use crate::merge::merge;
+
+    fn read(path: &str) -> Topology {
+        let file = File::open(path).expect("Could not load json file.");
+        serde_json::from_reader(file).expect("Did not parse correctly.")
+    }
+
+    fn line() -> Topology {
+        Topology {
+            arcs: vec![vec![
+                vec![0_f64, 0_f64],
+                vec![1_f64, 1_f64],
+                vec![2_f64, 0_f64],
+                vec![3_f64, 0_f64],
+                vec![4_f64, 0_f64],
+            ]],
+            objects: vec![NamedGeometry {
+                name: "line".to_string(),
+                geometry: topojson::Geometry::new(Value::LineString(vec![0])),
+            }],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn planar_ring_area_accepts_open_and_closed_rings() {
+        let closed = [
+            [0_f64, 0_f64],
+            [0_f64, 2_f64],
+            [3_f64, 2_f64],
+            [3_f64, 0_f64],
+            [0_f64, 0_f64],
+        ];
+        assert_eq!(planar_ring_area(&closed), 6_f64);
+        assert_eq!(planar_ring_area(&closed[..4]), 6_f64);
+        assert_eq!(planar_ring_area(&[]), 0_f64);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn spherical_ring_area_of_a_hemisphere() {
+        // Clockwise, seen from above the north pole.
+        let equator = [
+            [0_f64, 0_f64],
+            [-90_f64, 0_f64],
+            [180_f64, 0_f64],
+            [90_f64, 0_f64],
+            [0_f64, 0_f64],
+        ];
+        let northern = spherical_ring_area(&equator, false);
+        assert!((northern - TAU).abs() < 1e-9, "{northern}");
+
+        // A small clockwise square, and the same ring as a hole. Its edges are
+        // great circles, so it is close to, not exactly, a latitude band.
+        let square = [
+            [0_f64, 0_f64],
+            [0_f64, 1_f64],
+            [1_f64, 1_f64],
+            [1_f64, 0_f64],
+            [0_f64, 0_f64],
+        ];
+        let area = spherical_ring_area(&square, false);
+        let expected = 1_f64.to_radians() * 1_f64.to_radians().sin();
+        assert!((area / expected - 1_f64).abs() < 1e-4, "{area} {expected}");
+        let mut reversed = square;
+        reversed.reverse();
+        let hole = spherical_ring_area(&reversed, true);
+        assert!((hole - area).abs() < 1e-12, "{hole} {area}");
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn spherical_triangle_area_shrinks_away_from_the_equator() {
+        // An octant of the sphere.
+        let octant = [[0_f64, 0_f64], [90_f64, 0_f64], [0_f64, 90_f64]];
+        let area = spherical_triangle_area(&octant);
+        assert!((area - PI / 2_f64).abs() < 1e-9, "{area}");
+
+        let at = |lat: f64| [[0_f64, lat], [1_f64, lat + 1_f64], [2_f64, lat]];
+        let ratio = spherical_triangle_area(&at(60_f64))
+            / spherical_triangle_area(&at(0_f64));
+        assert!((ratio - 0.5_f64).abs() < 0.01_f64, "{ratio}");
+        assert!(
+            (planar_triangle_area(&at(60_f64))
+                - planar_triangle_area(&at(0_f64)))
+            .abs()
+                < 1e-9
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn planar_triangle_area_is_unsigned() {
+        let triangle = [[0_f64, 0_f64], [2_f64, 0_f64], [0_f64, 2_f64]];
+        assert_eq!(planar_triangle_area(&triangle), 2_f64);
+        let [a, b, c] = triangle;
+        assert_eq!(planar_triangle_area(&[a, c, b]), 2_f64);
+        assert_eq!(
+            planar_triangle_area(&[
+                [0_f64, 0_f64],
+                [1_f64, 1_f64],
+                [2_f64, 2_f64]
+            ]),
+            0_f64
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn presimplify_assigns_effective_weights() {
+        let presimplified = presimplify(&line(), planar_triangle_area);
+        // The middle position is removed first, with a weight of zero. The
+        // weight of the last position removed is raised to that of the
+        // position removed before it.
+        assert_eq!(
+            presimplified.arcs[0],
+            vec![
+                vec![0_f64, 0_f64, f64::INFINITY],
+                vec![1_f64, 1_f64, 1_f64],
+                vec![2_f64, 0_f64, 1_f64],
+                vec![3_f64, 0_f64, 0_f64],
+                vec![4_f64, 0_f64, f64::INFINITY],
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn simplify_keeps_positions_of_at_least_the_minimum_weight() {
+        let presimplified = presimplify(&line(), planar_triangle_area);
+
+        assert_eq!(
+            simplify(&presimplified, f64::MIN_POSITIVE).arcs[0],
+            vec![
+                vec![0_f64, 0_f64],
+                vec![1_f64, 1_f64],
+                vec![2_f64, 0_f64],
+                vec![4_f64, 0_f64],
+            ]
+        );
+        assert_eq!(
+            simplify(&presimplified, f64::INFINITY).arcs[0],
+            vec![vec![0_f64, 0_f64], vec![4_f64, 0_f64]]
+        );
+        // Positions without a weight are removed.
+        assert_eq!(simplify(&line(), 0_f64).arcs[0], Vec::<Vec<f64>>::new());
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn quantile_ignores_the_arc_endpoints() {
+        let presimplified = presimplify(&line(), planar_triangle_area);
+        assert_eq!(quantile(&presimplified, 0_f64), 1_f64);
+        assert_eq!(quantile(&presimplified, 0.5_f64), 1_f64);
+        assert_eq!(quantile(&presimplified, 0.75_f64), 0.5_f64);
+        assert_eq!(quantile(&presimplified, 1_f64), 0_f64);
+        assert_eq!(quantile(&line(), 0.5_f64), 0_f64);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn quantile_of_a_single_weight() {
+        let mut topology = line();
+        topology.arcs[0].truncate(3);
+        let presimplified = presimplify(&topology, planar_triangle_area);
+        assert_eq!(quantile(&presimplified, 0_f64), 1_f64);
+        assert_eq!(quantile(&presimplified, 0.5_f64), 1_f64);
+        assert_eq!(quantile(&presimplified, 1_f64), 1_f64);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn presimplify_decodes_quantized_points() {
+        let mut topology = line();
+        topology.transform = Some(TransformParams {
+            scale: [2_f64, 2_f64],
+            translate: [100_f64, 100_f64],
+        });
+        topology.objects.push(NamedGeometry {
+            name: "point".to_string(),
+            geometry: topojson::Geometry::new(Value::Point(vec![
+                10_f64, 10_f64,
+            ])),
+        });
+        let presimplified = presimplify(&topology, planar_triangle_area);
+
+        let o = &topology.objects[1].geometry.value;
+        assert_eq!(
+            presimplified.objects[1].geometry.value,
+            Value::Point(vec![120_f64, 120_f64])
+        );
+        assert_eq!(
+            feature::<f64>(
+                &presimplified,
+                &presimplified.objects[1].geometry.value
+            ),
+            feature::<f64>(&topology, o)
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn presimplified_topologies_can_be_decoded() {
+        let topology = read("./tests/topojson/polygon-q1e4.json");
+        let presimplified = presimplify(&topology, planar_triangle_area);
+        assert_eq!(presimplified.transform, None);
+
+        let o = &topology.objects[0].geometry.value;
+        assert_eq!(
+            feature::<f64>(&presimplified, o),
+            feature::<f64>(&topology, o)
+        );
+        assert_eq!(
+            merge::<f64>(&presimplified, &presimplified.objects),
+            merge::<f64>(&topology, &topology.objects)
+        );
+
+        let simplified =
+            simplify(&presimplified, quantile(&presimplified, 1_f64));
+        assert_eq!(
+            feature::<f64>(&simplified, o),
+            feature::<f64>(&topology, o)
+        );
+    }
+}
@@ -1,9 +1,7 @@
-use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::num::Wrapping;
-use std::rc::Rc;
 
 use topojson::{ArcIndexes, Topology};
 
@@ -15,9 +13,10 @@ pub(super) fn stitch(
 ) -> Vec<ArcIndexes> {
     let mut stitch = Stitch {
         stitched_arcs: HashSet::new(),
-        fragment_by_start: BTreeMap::new(),
-        fragment_by_end: BTreeMap::new(),
+        fragment_by_start: FragmentMap::default(),
+        fragment_by_end: FragmentMap::default(),
         fragments: vec![],
+        pool: vec![],
         topology,
     };
 
@@ -39,99 +38,87 @@ pub(super) fn stitch(
 
     for i in &arcs {
         let e = stitch.ends(*i);
-        // TODO could I use  or_default() instead of .unwrap()
-        let start: FragmentKey = gen_key(e.first().unwrap());
-        let end = gen_key(e.get(1).unwrap());
-
-        if let Some(f) = stitch.fragment_by_end.clone().get(&start) {
-            let key = *f.clone().borrow_mut().end.as_ref().unwrap();
-            stitch.fragment_by_end.remove(&key);
-            f.borrow_mut().items.push_back(*i);
-            f.borrow_mut().end = Some(end);
-
-            if let Some(g) = stitch.fragment_by_start.get(&end) {
-                let g = g.clone();
+        let start: FragmentKey = gen_key(&e[0]);
+        let end = gen_key(&e[1]);
+
+        if let Some(f) = stitch.fragment_by_end.get(start) {
+            let key = stitch.pool[f].end.unwrap();
+            stitch.fragment_by_end.remove(key);
+            stitch.pool[f].items.push_back(*i);
+            stitch.pool[f].end = Some(end);
+
+            if let Some(g) = stitch.fragment_by_start.get(end) {
                 stitch
                     .fragment_by_start
-                    .remove(&(g).borrow_mut().start.as_ref().unwrap().clone());
+                    .remove(stitch.pool[g].start.unwrap());
 
-                let fg = if g == *f {
-                    f.clone()
+                let fg = if g == f {
+                    f
                 } else {
-                    let g_items = g.borrow().items.clone();
-                    let items = f
-                        .borrow_mut()
+                    let items = stitch.pool[f]
                         .items
                         .iter()
-                        .chain(g_items.iter())
+                        .chain(stitch.pool[g].items.iter())
                         .copied()
                         .collect();
-                    let g_end = (g).borrow().end.unwrap();
-                    Rc::new(RefCell::new(Fragment {
+                    stitch.push(Fragment {
                         items,
-                        start: f.borrow_mut().start,
-                        end: Some(g_end),
-                    }))
+                        start: stitch.pool[f].start,
+                        end: stitch.pool[g].end,
+                    })
                 };
-                let key = *f.clone().borrow_mut().end.as_ref().unwrap();
-                stitch.fragment_by_start.insert(key, fg.clone());
-                let key = fg.borrow_mut().end.unwrap();
+                let key = stitch.pool[f].start.unwrap();
+                stitch.fragment_by_start.insert(key, fg);
+                let key = stitch.pool[fg].end.unwrap();
                 stitch.fragment_by_end.insert(key, fg);
             } else {
-                stitch
-                    .fragment_by_start
-                    .insert(f.borrow_mut().start.unwrap(), f.clone());
-                stitch
-                    .fragment_by_end
-                    .insert(f.borrow_mut().end.unwrap(), f.clone());
+                let key = stitch.pool[f].start.unwrap();
+                stitch.fragment_by_start.insert(key, f);
+                let key = stitch.pool[f].end.unwrap();
+                stitch.fragment_by_end.insert(key, f);
             }
-        } else if let Some(f) = stitch.fragment_by_start.get(&end) {
-            let f = f.clone();
-            let key = *f.borrow_mut().start.as_ref().unwrap();
-            stitch.fragment_by_start.remove(&key);
-            f.borrow_mut().items.push_front(*i);
-            f.borrow_mut().start = Some(start);
-
-            if let Some(g) = stitch.fragment_by_end.get(&start) {
-                let g = g.clone();
-                stitch
-                    .fragment_by_end
-                    .remove(g.borrow().end.as_ref().unwrap());
+        } else if let Some(f) = stitch.fragment_by_start.get(end) {
+            let key = stitch.pool[f].start.unwrap();
+            stitch.fragment_by_start.remove(key);
+            stitch.pool[f].items.push_front(*i);
+            stitch.pool[f].start = Some(start);
+
+            if let Some(g) = stitch.fragment_by_end.get(start) {
+                stitch.fragment_by_end.remove(stitch.pool[g].end.unwrap());
 
                 let gf = if g == f {
                     f
                 } else {
-                    let g_then_f = g
-                        .borrow()
+                    let items = stitch.pool[g]
                         .items
-                        .clone()
-                        .into_iter()
-                        .chain(f.borrow_mut().items.clone());
-
-                    Rc::new(RefCell::new(Fragment {
-                        items: g_then_f.collect::<VecDeque<_>>(),
-                        start: g.borrow().start,
-                        end: f.borrow_mut().end,
-                    }))
+                        .iter()
+                        .chain(stitch.pool[f].items.iter())
+                        .copied()
+                        .collect();
+                    stitch.push(Fragment {
+                        items,
+                        start: stitch.pool[g].start,
+                        end: stitch.pool[f].end,
+                    })
                 };
 
-                let key = gf.borrow().start.unwrap();
-                stitch.fragment_by_start.insert(key, gf.clone());
-                let key = gf.borrow().end.unwrap();
+                let key = stitch.pool[gf].start.unwrap();
+                stitch.fragment_by_start.insert(key, gf);
+                let key = stitch.pool[gf].end.unwrap();
                 stitch.fragment_by_end.insert(key, gf);
             } else {
-                let key = f.borrow_mut().start.unwrap();
-                stitch.fragment_by_start.insert(key, f.clone());
-                let key = f.borrow_mut().end.unwrap();
+                let key = stitch.pool[f].start.unwrap();
+                stitch.fragment_by_start.insert(key, f);
+                let key = stitch.pool[f].end.unwrap();
                 stitch.fragment_by_end.insert(key, f);
             }
         } else {
-            let f = Rc::new(RefCell::new(Fragment {
+            let f = stitch.push(Fragment {
                 items: VecDeque::from(vec![*i]),
                 start: Some(start),
                 end: Some(end),
-            }));
-            stitch.fragment_by_start.insert(start, f.clone());
+            });
+            stitch.fragment_by_start.insert(start, f);
             stitch.fragment_by_end.insert(end, f);
         }
     }
@@ -159,9 +146,12 @@ pub(super) fn stitch(
 }
 
 // Returns a key, used in the Fragment struct.
-fn gen_key(input: &[f64]) -> FragmentKey {
-    debug_assert_eq!(input.len(), 2);
-    (input[0] as i32, input[1] as i32)
+//
+// The key is exact, as the string key is in javascript, so that arcs of a
+// topology which is not quantized are only joined at identical points.
+// Adding zero maps -0 onto 0, which javascript also formats as "0".
+pub(super) fn gen_key(input: &[f64; 2]) -> FragmentKey {
+    ((input[0] + 0_f64).to_bits(), (input[1] + 0_f64).to_bits())
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -171,14 +161,60 @@ struct Fragment {
     end: Option<FragmentKey>,
 }
 
-type FragmentKey = (i32, i32);
+type FragmentKey = (u64, u64);
+
+/// Fragments indexed by their start or end point, as indexes into
+/// `Stitch::pool`.
+///
+/// In javascript the equivalent object is iterated in insertion order, and
+/// the output of `flush()` depends on it. So each entry records when its key
+/// was first inserted.
+#[derive(Clone, Debug, Default)]
+struct FragmentMap {
+    next: usize,
+    map: BTreeMap<FragmentKey, (usize, usize)>,
+}
+
+impl FragmentMap {
+    fn get(&self, key: FragmentKey) -> Option<usize> {
+        self.map.get(&key).map(|(_, f)| *f)
+    }
+
+    /// Replacing the fragment of an existing key preserves its position.
+    fn insert(&mut self, key: FragmentKey, f: usize) {
+        if let Some(entry) = self.map.get_mut(&key) {
+            entry.1 = f;
+        } else {
+            self.map.insert(key, (self.next, f));
+            self.next += 1;
+        }
+    }
+
+    fn remove(&mut self, key: FragmentKey) {
+        self.map.remove(&key);
+    }
+
+    /// Keys in insertion order.
+    fn keys(&self) -> Vec<FragmentKey> {
+        let mut keys: Vec<(usize, FragmentKey)> = self
+            .map
+            .iter()
+            .map(|(k, (order, _))| (*order, *k))
+            .collect();
+        keys.sort_unstable();
+        keys.into_iter().map(|(_, k)| k).collect()
+    }
+}
 
 #[derive(Clone, Debug)]
 struct Stitch<'a> {
     stitched_arcs: HashSet<usize>,
-    fragment_by_start: BTreeMap<FragmentKey, Rc<RefCell<Fragment>>>,
-    fragment_by_end: BTreeMap<FragmentKey, Rc<RefCell<Fragment>>>,
+    fragment_by_start: FragmentMap,
+    fragment_by_end: FragmentMap,
     fragments: Vec<Fragment>,
+    // Every fragment created, a fragment is shared between the two maps by
+    // its index.
+    pool: Vec<Fragment>,
     topology: &'a Topology,
 }
 
@@ -188,26 +224,29 @@ enum FlushDir {
 }
 
 impl Stitch<'_> {
+    /// Adds a fragment to the pool, returning its index.
+    fn push(&mut self, f: Fragment) -> usize {
+        self.pool.push(f);
+        self.pool.len() - 1
+    }
+
     // Stitch empty arcs first, since they may be subsumed by other arcs.
-    fn ends(&self, i: i32) -> Vec<Vec<f64>> {
+    fn ends(&self, i: i32) -> [[f64; 2]; 2] {
         let arc = &self.topology.arcs[translate(i)];
-        let p0 = arc[0].clone();
-        let mut p1: Vec<f64>;
+        let p0 = [arc[0][0], arc[0][1]];
+        let mut p1: [f64; 2];
 
         if self.topology.transform.is_some() {
-            p1 = vec![0_f64, 0_f64];
+            p1 = [0_f64, 0_f64];
             for dp in arc {
                 p1[0] += dp[0];
                 p1[1] += dp[1];
             }
         } else {
-            p1 = arc.last().unwrap().clone();
-        }
-        if i < 0 {
-            vec![p1, p0]
-        } else {
-            vec![p0, p1]
+            let last = &arc[arc.len() - 1];
+            p1 = [last[0], last[1]];
         }
+        if i < 0 { [p1, p0] } else { [p0, p1] }
     }
 
     /// Iterate over `fragment_by_end` :-
@@ -223,15 +262,11 @@ impl Stitch<'_> {
             }
         };
 
-        let search_iterator =
-            fragment_by_end.keys().copied().collect::<Vec<(i32, i32)>>();
-        for k in search_iterator {
-            let f = fragment_by_end.get(&k).unwrap().clone();
-
-            fragment_by_start.remove(&k);
-            // fragment_by_end.remove(&cross_link);
-
-            let mut f = f.borrow_mut();
+        for k in fragment_by_end.keys() {
+            let f = &mut self.pool[fragment_by_end.get(k).unwrap()];
+            if let Some(start) = f.start {
+                fragment_by_start.remove(start);
+            }
             f.start = None;
             f.end = None;
 
@@ -242,3 +277,43 @@ impl Stitch<'_> {
         }
     }
 }
+
+#[cfg(test)]
+mod stitch_tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn keys_are_exact() {
+        assert_ne!(gen_key(&[10.2_f64, 0_f64]), gen_key(&[10.7_f64, 0_f64]));
+        assert_ne!(gen_key(&[0_f64, 0.1_f64]), gen_key(&[0_f64, 0.2_f64]));
+        assert_eq!(gen_key(&[-0_f64, 1.5_f64]), gen_key(&[0_f64, 1.5_f64]));
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn does_not_join_arcs_at_nearby_points() {
+        let topology = Topology {
+            arcs: vec![
+                vec![vec![10.2_f64, 0_f64], vec![20_f64, 0_f64]],
+                vec![vec![10.7_f64, 0_f64], vec![0_f64, 0_f64]],
+                vec![vec![20_f64, 0_f64], vec![30_f64, 0_f64]],
+            ],
+            objects: vec![],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(stitch(&topology, vec![0, 1, 2]), vec![vec![1], vec![0, 2]]);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    const fn can_run_on_worker_threads() {
+        const fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Stitch<'_>>();
+    }
+}
@@ -0,0 +1,909 @@
+use std::collections::{HashMap, HashSet};
+
+use geo::{CoordsIter, Geometry, LineString, Polygon};
+use topojson::{ArcIndexes, NamedGeometry, Topology, TransformParams, Value};
+
+use crate::stitch::gen_key;
+
+type PointKey = (u64, u64);
+
+/// Builds a topology from named geometries, a port of topojson-server.
+///
+/// Lines and polygon rings are cut at junctions, the points where they
+/// meet or diverge, and arcs shared by several geometries are stored once.
+/// An arc used in the opposite direction is referenced by the ones'
+/// complement of its index. `Line` becomes a `LineString`, `Rect` and
+/// `Triangle` become a `Polygon`.
+///
+/// With `Some(n)`, positions are first quantized onto an `n` by `n` grid
+/// over the bounding box, coincident consecutive positions are removed and
+/// the arcs are delta-encoded. A quantization below two is ignored.
+///
+/// [`feature`](crate::feature::feature) returns the input geometry of each
+/// object, except that rings may start at a different position, as a ring
+/// is rotated to start at a junction rather than being cut there.
+///
+/// # Panics
+///
+/// When there are more than `i32::MAX` arcs.
+#[must_use]
+pub fn topology(
+    objects: Vec<(String, Geometry)>,
+    quantization: Option<u32>,
+) -> Topology {
+    let bbox = bounds(&objects);
+    let quantizer = match (quantization, bbox) {
+        (Some(n), Some(bbox)) if n >= 2 => Some(Quantizer::new(bbox, n)),
+        _ => None,
+    };
+
+    let mut extract = Extract {
+        quantizer,
+        coordinates: vec![],
+        lines: vec![],
+        rings: vec![],
+    };
+    let shapes: Vec<(String, Shape)> = objects
+        .into_iter()
+        .map(|(name, g)| {
+            let shape = extract.geometry(&g);
+            (name, shape)
+        })
+        .collect();
+
+    let Extract {
+        coordinates,
+        lines,
+        rings,
+        ..
+    } = extract;
+    let mut cut = Cut {
+        coordinates,
+        lines,
+        rings,
+    };
+    cut.cut();
+
+    let mut dedup = Dedup {
+        coordinates: &cut.coordinates,
+        arcs: vec![],
+        arcs_by_end: HashMap::new(),
+    };
+    let mut lines: Vec<ArcIndexes> = cut
+        .lines
+        .iter()
+        .map(|chain| chain.iter().map(|arc| dedup.line(*arc)).collect())
+        .collect();
+    let mut rings: Vec<ArcIndexes> = cut
+        .rings
+        .iter()
+        .map(|chain| match chain.as_slice() {
+            // The arc is still closed.
+            [ring] => vec![dedup.ring(*ring)],
+            _ => chain.iter().map(|arc| dedup.line(*arc)).collect(),
+        })
+        .collect();
+
+    let mut arcs: Vec<Vec<Vec<f64>>> = dedup
+        .arcs
+        .iter()
+        .map(|[start, end]| {
+            cut.coordinates[*start..=*end]
+                .iter()
+                .map(|p| p.to_vec())
+                .collect()
+        })
+        .collect();
+
+    let objects = shapes
+        .into_iter()
+        .map(|(name, shape)| NamedGeometry {
+            name,
+            geometry: shape.into_geometry(&mut lines, &mut rings),
+        })
+        .collect();
+
+    let transform = quantizer.map(|q| {
+        delta(&mut arcs);
+        q.transform()
+    });
+
+    Topology {
+        bbox: bbox.map(Vec::from),
+        objects,
+        transform,
+        arcs,
+        foreign_members: None,
+    }
+}
+
+/// Returns the bounding box of all positions, `[x0, y0, x1, y1]`.
+fn bounds(objects: &[(String, Geometry)]) -> Option<[f64; 4]> {
+    let mut x0 = f64::INFINITY;
+    let mut y0 = f64::INFINITY;
+    let mut x1 = f64::NEG_INFINITY;
+    let mut y1 = f64::NEG_INFINITY;
+    for c in objects.iter().flat_map(|(_, g)| g.coords_iter()) {
+        if c.x < x0 {
+            x0 = c.x;
+        }
+        if c.x > x1 {
+            x1 = c.x;
+        }
+        if c.y < y0 {
+            y0 = c.y;
+        }
+        if c.y > y1 {
+            y1 = c.y;
+        }
+    }
+    (x1 >= x0 && y1 >= y0).then_some([x0, y0, x1, y1])
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Quantizer {
+    x0: f64,
+    y0: f64,
+    kx: f64,
+    ky: f64,
+}
+
+impl Quantizer {
+    fn new([x0, y0, x1, y1]: [f64; 4], n: u32) -> Self {
+        let steps = f64::from(n - 1);
+        Self {
+            x0,
+            y0,
+            kx: if x1 - x0 == 0_f64 {
+                1_f64
+            } else {
+                steps / (x1 - x0)
+            },
+            ky: if y1 - y0 == 0_f64 {
+                1_f64
+            } else {
+                steps / (y1 - y0)
+            },
+        }
+    }
+
+    fn point(&self, x: f64, y: f64) -> [f64; 2] {
+        [
+            ((x - self.x0) * self.kx).round(),
+            ((y - self.y0) * self.ky).round(),
+        ]
+    }
+
+    fn transform(&self) -> TransformParams {
+        TransformParams {
+            scale: [1_f64 / self.kx, 1_f64 / self.ky],
+            translate: [self.x0, self.y0],
+        }
+    }
+}
+
+/// A geometry whose lines and rings have been extracted, referenced by
+/// their index in `Extract::lines` or `Extract::rings`.
+enum Shape {
+    Empty,
+    Collection(Vec<Self>),
+    Point(Vec<f64>),
+    MultiPoint(Vec<Vec<f64>>),
+    LineString(usize),
+    MultiLineString(Vec<usize>),
+    Polygon(Vec<usize>),
+    MultiPolygon(Vec<Vec<usize>>),
+}
+
+impl Shape {
+    fn into_geometry(
+        self,
+        lines: &mut [ArcIndexes],
+        rings: &mut [ArcIndexes],
+    ) -> topojson::Geometry {
+        let value = match self {
+            Self::Empty => Value::GeometryCollection(vec![]),
+            Self::Collection(shapes) => Value::GeometryCollection(
+                shapes
+                    .into_iter()
+                    .map(|s| s.into_geometry(lines, rings))
+                    .collect(),
+            ),
+            Self::Point(p) => Value::Point(p),
+            Self::MultiPoint(mp) => Value::MultiPoint(mp),
+            Self::LineString(i) => {
+                Value::LineString(std::mem::take(&mut lines[i]))
+            }
+            Self::MultiLineString(ls) => Value::MultiLineString(
+                ls.into_iter()
+                    .map(|i| std::mem::take(&mut lines[i]))
+                    .collect(),
+            ),
+            Self::Polygon(p) => Value::Polygon(
+                p.into_iter()
+                    .map(|i| std::mem::take(&mut rings[i]))
+                    .collect(),
+            ),
+            Self::MultiPolygon(mp) => Value::MultiPolygon(
+                mp.into_iter()
+                    .map(|p| {
+                        p.into_iter()
+                            .map(|i| std::mem::take(&mut rings[i]))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        };
+        topojson::Geometry::new(value)
+    }
+}
+
+/// A chain of arcs, each arc being the inclusive range `[start, end]` of
+/// its positions in the coordinates.
+type Chain = Vec<[usize; 2]>;
+
+/// Copies the positions of every line and ring into a single array.
+struct Extract {
+    quantizer: Option<Quantizer>,
+    coordinates: Vec<[f64; 2]>,
+    lines: Vec<Chain>,
+    rings: Vec<Chain>,
+}
+
+impl Extract {
+    fn geometry(&mut self, g: &Geometry) -> Shape {
+        match g {
+            Geometry::GeometryCollection(gc) => {
+                Shape::Collection(gc.iter().map(|g| self.geometry(g)).collect())
+            }
+            Geometry::Point(p) => Shape::Point(self.point(p.x(), p.y())),
+            Geometry::MultiPoint(mp) => Shape::MultiPoint(
+                mp.iter().map(|p| self.point(p.x(), p.y())).collect(),
+            ),
+            Geometry::Line(line) => self.line_string(&LineString::from(*line)),
+            Geometry::LineString(ls) => self.line_string(ls),
+            Geometry::MultiLineString(mls) => Shape::MultiLineString(
+                mls.iter().filter_map(|ls| self.line(ls)).collect(),
+            ),
+            Geometry::Polygon(polygon) => Shape::Polygon(self.polygon(polygon)),
+            Geometry::MultiPolygon(mp) => Shape::MultiPolygon(
+                mp.iter().map(|polygon| self.polygon(polygon)).collect(),
+            ),
+            Geometry::Rect(rect) => {
+                Shape::Polygon(self.polygon(&rect.to_polygon()))
+            }
+            Geometry::Triangle(triangle) => {
+                Shape::Polygon(self.polygon(&triangle.to_polygon()))
+            }
+        }
+    }
+
+    fn point(&self, x: f64, y: f64) -> Vec<f64> {
+        self.quantizer.map_or([x, y], |q| q.point(x, y)).to_vec()
+    }
+
+    fn line_string(&mut self, ls: &LineString) -> Shape {
+        self.line(ls).map_or(Shape::Empty, Shape::LineString)
+    }
+
+    fn line(&mut self, ls: &LineString) -> Option<usize> {
+        let arc = self.positions(ls, 2)?;
+        self.lines.push(vec![arc]);
+        Some(self.lines.len() - 1)
+    }
+
+    /// Returns the rings, an empty polygon when the exterior is empty.
+    fn polygon(&mut self, polygon: &Polygon) -> Vec<usize> {
+        if polygon.exterior().0.is_empty() {
+            return vec![];
+        }
+        std::iter::once(polygon.exterior())
+            .chain(polygon.interiors())
+            .filter_map(|ring| {
+                let arc = self.positions(ring, 4)?;
+                self.rings.push(vec![arc]);
+                Some(self.rings.len() - 1)
+            })
+            .collect()
+    }
+
+    /// Appends the positions, padded to at least `min` positions, and
+    /// returns their range.
+    #[allow(clippy::float_cmp)]
+    fn positions(&mut self, ls: &LineString, min: usize) -> Option<[usize; 2]> {
+        let start = self.coordinates.len();
+        for c in &ls.0 {
+            match self.quantizer {
+                Some(q) => {
+                    let p = q.point(c.x, c.y);
+                    // Remove coincident points.
+                    if self.coordinates.len() == start
+                        || self.coordinates.last() != Some(&p)
+                    {
+                        self.coordinates.push(p);
+                    }
+                }
+                None => self.coordinates.push([c.x, c.y]),
+            }
+        }
+
+        let first = *self.coordinates.get(start)?;
+        while self.coordinates.len() - start < min {
+            self.coordinates.push(first);
+        }
+        Some([start, self.coordinates.len() - 1])
+    }
+}
+
+/// Cuts the lines and rings at junctions.
+struct Cut {
+    coordinates: Vec<[f64; 2]>,
+    lines: Vec<Chain>,
+    rings: Vec<Chain>,
+}
+
+impl Cut {
+    fn cut(&mut self) {
+        let junctions = self.join();
+
+        for chain in &mut self.lines {
+            let [start, end] = chain[0];
+            let mut arcs = vec![];
+            let mut first = start;
+            for mid in start + 1..end {
+                if junctions.contains(&gen_key(&self.coordinates[mid])) {
+                    arcs.push([first, mid]);
+                    first = mid;
+                }
+            }
+            arcs.push([first, end]);
+            *chain = arcs;
+        }
+
+        for chain in &mut self.rings {
+            let [start, end] = chain[0];
+            let mut fixed =
+                junctions.contains(&gen_key(&self.coordinates[start]));
+            let mut arcs = vec![];
+            let mut first = start;
+            let mut mid = start + 1;
+            while mid < end {
+                if junctions.contains(&gen_key(&self.coordinates[mid])) {
+                    if fixed {
+                        arcs.push([first, mid]);
+                        first = mid;
+                    } else {
+                        // For the first junction, we can rotate rather than
+                        // cut.
+                        self.coordinates[start..end].rotate_left(mid - start);
+                        self.coordinates[end] = self.coordinates[start];
+                        fixed = true;
+                        // Restart, we may have skipped junctions.
+                        mid = start;
+                    }
+                }
+                mid += 1;
+            }
+            arcs.push([first, end]);
+            *chain = arcs;
+        }
+    }
+
+    /// Returns the junctions: the ends of lines, and the points where
+    /// lines and rings meet or diverge.
+    fn join(&self) -> HashSet<PointKey> {
+        let n = self.coordinates.len();
+
+        // The index of the first coincident position.
+        let mut index_by_point: HashMap<PointKey, usize> = HashMap::new();
+        let indexes: Vec<usize> = self
+            .coordinates
+            .iter()
+            .enumerate()
+            .map(|(i, p)| *index_by_point.entry(gen_key(p)).or_insert(i))
+            .collect();
+
+        let mut visited: Vec<Option<usize>> = vec![None; n];
+        let mut neighbors: Vec<Option<(usize, usize)>> = vec![None; n];
+        let mut junction = vec![false; n];
+
+        let mut sequence =
+            |i: usize,
+             previous: usize,
+             current: usize,
+             next: usize,
+             visited: &mut [Option<usize>]| {
+                // Ignore self-intersection.
+                if visited[current] == Some(i) {
+                    return;
+                }
+                visited[current] = Some(i);
+                match neighbors[current] {
+                    Some((left, right)) => {
+                        if (left != previous || right != next)
+                            && (left != next || right != previous)
+                        {
+                            junction[current] = true;
+                        }
+                    }
+                    None => neighbors[current] = Some((previous, next)),
+                }
+            };
+
+        let mut line_ends = vec![];
+        for (i, chain) in self.lines.iter().enumerate() {
+            let [start, end] = chain[0];
+            line_ends.push(indexes[start]);
+            line_ends.push(indexes[end]);
+            for k in start + 1..end {
+                sequence(
+                    i,
+                    indexes[k - 1],
+                    indexes[k],
+                    indexes[k + 1],
+                    &mut visited,
+                );
+            }
+        }
+
+        visited.fill(None);
+
+        for (i, chain) in self.rings.iter().enumerate() {
+            let [start, end] = chain[0];
+            sequence(
+                i,
+                indexes[end - 1],
+                indexes[start],
+                indexes[start + 1],
+                &mut visited,
+            );
+            for k in start + 1..end {
+                sequence(
+                    i,
+                    indexes[k - 1],
+                    indexes[k],
+                    indexes[k + 1],
+                    &mut visited,
+                );
+            }
+        }
+
+        for i in line_ends {
+            junction[i] = true;
+        }
+
+        junction
+            .iter()
+            .zip(&self.coordinates)
+            .filter(|(j, _)| **j)
+            .map(|(_, p)| gen_key(p))
+            .collect()
+    }
+}
+
+/// Identifies the arcs which are shared, in either direction.
+struct Dedup<'a> {
+    coordinates: &'a [[f64; 2]],
+    arcs: Vec<[usize; 2]>,
+    arcs_by_end: HashMap<PointKey, Vec<usize>>,
+}
+
+impl Dedup<'_> {
+    /// Returns the index of the arc, reusing an existing arc if possible.
+    fn line(&mut self, arc: [usize; 2]) -> i32 {
+        let start_point = gen_key(&self.coordinates[arc[0]]);
+        let end_point = gen_key(&self.coordinates[arc[1]]);
+
+        // Does this arc match an existing arc in order?
+        if let Some(&k) =
+            self.arcs_by_end.get(&start_point).and_then(|starts| {
+                starts.iter().find(|k| self.equal_line(self.arcs[**k], arc))
+            })
+        {
+            return index(k);
+        }
+
+        // Does this arc match an existing arc in reverse order?
+        if let Some(&k) = self.arcs_by_end.get(&end_point).and_then(|ends| {
+            ends.iter()
+                .find(|k| self.reverse_equal_line(self.arcs[**k], arc))
+        }) {
+            return !index(k);
+        }
+
+        let k = self.arcs.len();
+        self.arcs_by_end.entry(start_point).or_default().push(k);
+        self.arcs_by_end.entry(end_point).or_default().push(k);
+        self.arcs.push(arc);
+        index(k)
+    }
+
+    /// Returns the index of the closed arc, reusing an existing arc if
+    /// possible.
+    fn ring(&mut self, arc: [usize; 2]) -> i32 {
+        // Does this arc match an existing line in order, or reverse order?
+        // Rings are closed, so their start point and end point is the same.
+        let start_point = gen_key(&self.coordinates[arc[0]]);
+        if let Some(k) = self.find_ring(start_point, arc) {
+            return k;
+        }
+
+        // Otherwise, does this arc match an existing ring in order, or
+        // reverse order?
+        let end_point =
+            gen_key(&self.coordinates[arc[0] + self.minimum_offset(arc)]);
+        if let Some(k) = self.find_ring(end_point, arc) {
+            return k;
+        }
+
+        let k = self.arcs.len();
+        self.arcs_by_end.entry(end_point).or_default().push(k);
+        self.arcs.push(arc);
+        index(k)
+    }
+
+    fn find_ring(&self, point: PointKey, arc: [usize; 2]) -> Option<i32> {
+        self.arcs_by_end.get(&point)?.iter().find_map(|k| {
+            let other = self.arcs[*k];
+            if self.equal_ring(other, arc) {
+                Some(index(*k))
+            } else if self.reverse_equal_ring(other, arc) {
+                Some(!index(*k))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn equal_point(&self, i: usize, j: usize) -> bool {
+        gen_key(&self.coordinates[i]) == gen_key(&self.coordinates[j])
+    }
+
+    fn equal_line(&self, [ia, ja]: [usize; 2], [ib, jb]: [usize; 2]) -> bool {
+        ja - ia == jb - ib
+            && (0..=ja - ia).all(|i| self.equal_point(ia + i, ib + i))
+    }
+
+    fn reverse_equal_line(
+        &self,
+        [ia, ja]: [usize; 2],
+        [ib, jb]: [usize; 2],
+    ) -> bool {
+        ja - ia == jb - ib
+            && (0..=ja - ia).all(|i| self.equal_point(ia + i, jb - i))
+    }
+
+    fn equal_ring(&self, a: [usize; 2], b: [usize; 2]) -> bool {
+        let n = a[1] - a[0];
+        if n != b[1] - b[0] {
+            return false;
+        }
+        let ka = self.minimum_offset(a);
+        let kb = self.minimum_offset(b);
+        (0..n)
+            .all(|i| self.equal_point(a[0] + (i + ka) % n, b[0] + (i + kb) % n))
+    }
+
+    fn reverse_equal_ring(&self, a: [usize; 2], b: [usize; 2]) -> bool {
+        let n = a[1] - a[0];
+        if n != b[1] - b[0] {
+            return false;
+        }
+        let ka = self.minimum_offset(a);
+        let kb = n - self.minimum_offset(b);
+        (0..n)
+            .all(|i| self.equal_point(a[0] + (i + ka) % n, b[1] - (i + kb) % n))
+    }
+
+    /// Rings are rotated to a consistent, but arbitrary, start point.
+    /// This is necessary to detect when a ring and a rotated copy are dupes.
+    #[allow(clippy::float_cmp)]
+    fn minimum_offset(&self, [start, end]: [usize; 2]) -> usize {
+        let mut minimum = start;
+        for mid in start + 1..end {
+            let p = self.coordinates[mid];
+            let m = self.coordinates[minimum];
+            if p[0] < m[0] || (p[0] == m[0] && p[1] < m[1]) {
+                minimum = mid;
+            }
+        }
+        minimum - start
+    }
+}
+
+fn index(k: usize) -> i32 {
+    i32::try_from(k).expect("the number of arcs must fit in an i32")
+}
+
+/// Delta-encodes the quantized arcs, removing coincident points.
+#[allow(clippy::float_cmp)]
+fn delta(arcs: &mut [Vec<Vec<f64>>]) {
+    for arc in arcs {
+        let [mut x0, mut y0] = [arc[0][0], arc[0][1]];
+        let mut k = 1;
+        for j in 1..arc.len() {
+            let [x1, y1] = [arc[j][0], arc[j][1]];
+            if x1 != x0 || y1 != y0 {
+                arc[k] = vec![x1 - x0, y1 - y0];
+                k += 1;
+                x0 = x1;
+                y0 = y1;
+            }
+        }
+        // Each arc must be an array of two or more positions.
+        if k == 1 {
+            arc[k] = vec![0_f64, 0_f64];
+            k += 1;
+        }
+        arc.truncate(k);
+    }
+}
+
+#[cfg(test)]
+mod topology_tests {
+    use geo::{
+        Coord, Line, MultiLineString, Rect, line_string, point, polygon,
+    };
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::feature::feature;
+
+    fn objects(geometries: Vec<Geometry>) -> Vec<(String, Geometry)> {
+        geometries
+            .into_iter()
+            .enumerate()
+            .map(|(i, g)| (format!("o{i}"), g))
+            .collect()
+    }
+
+    fn values(topology: &Topology) -> Vec<Value> {
+        topology
+            .objects
+            .iter()
+            .map(|o| o.geometry.value.clone())
+            .collect()
+    }
+
+    fn arcs(arcs: &[&[[f64; 2]]]) -> Vec<Vec<Vec<f64>>> {
+        arcs.iter()
+            .map(|arc| arc.iter().map(|p| p.to_vec()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn exact_duplicate_lines_share_an_arc() {
+        println!("topology exact duplicate lines ABC & ABC share an arc");
+        let abc = line_string![(x: 0., y: 0.), (x: 1., y: 0.), (x: 2., y: 0.)];
+        let t = topology(objects(vec![abc.clone().into(), abc.into()]), None);
+        assert_eq!(t.arcs, arcs(&[&[[0., 0.], [1., 0.], [2., 0.]]]));
+        assert_eq!(
+            values(&t),
+            vec![Value::LineString(vec![0]), Value::LineString(vec![0])]
+        );
+    }
+
+    #[test]
+    fn reversed_duplicate_lines_share_an_arc() {
+        println!("topology reversed duplicate lines ABC & CBA share an arc");
+        let t = topology(
+            objects(vec![
+                line_string![(x: 0., y: 0.), (x: 1., y: 0.), (x: 2., y: 0.)]
+                    .into(),
+                line_string![(x: 2., y: 0.), (x: 1., y: 0.), (x: 0., y: 0.)]
+                    .into(),
+            ]),
+            None,
+        );
+        assert_eq!(t.arcs, arcs(&[&[[0., 0.], [1., 0.], [2., 0.]]]));
+        assert_eq!(
+            values(&t),
+            vec![Value::LineString(vec![0]), Value::LineString(vec![-1])]
+        );
+    }
+
+    #[test]
+    fn old_arc_extending_a_new_arc_is_cut() {
+        println!(
+            "topology when an old arc ABC extends a new arc AB, they are cut into AB.BC and AB"
+        );
+        let t = topology(
+            objects(vec![
+                line_string![(x: 0., y: 0.), (x: 1., y: 0.), (x: 2., y: 0.)]
+                    .into(),
+                line_string![(x: 0., y: 0.), (x: 1., y: 0.)].into(),
+            ]),
+            None,
+        );
+        assert_eq!(
+            t.arcs,
+            arcs(&[&[[0., 0.], [1., 0.]], &[[1., 0.], [2., 0.]]])
+        );
+        assert_eq!(
+            values(&t),
+            vec![Value::LineString(vec![0, 1]), Value::LineString(vec![0])]
+        );
+    }
+
+    #[test]
+    fn duplicate_rings_share_an_arc() {
+        println!("topology exact duplicate rings ABCA & ABCA share an arc");
+        println!("topology reversed duplicate rings ACBA & ABCA share an arc");
+        println!("topology rotated duplicate rings BCAB & ABCA share an arc");
+        let abca = polygon![(x: 0., y: 0.), (x: 1., y: 0.), (x: 0., y: 1.)];
+        let acba = polygon![(x: 0., y: 0.), (x: 0., y: 1.), (x: 1., y: 0.)];
+        let bcab = polygon![(x: 1., y: 0.), (x: 0., y: 1.), (x: 0., y: 0.)];
+        let t = topology(
+            objects(vec![
+                abca.clone().into(),
+                abca.into(),
+                acba.into(),
+                bcab.into(),
+            ]),
+            None,
+        );
+        assert_eq!(t.arcs, arcs(&[&[[0., 0.], [1., 0.], [0., 1.], [0., 0.]]]));
+        assert_eq!(
+            values(&t),
+            vec![
+                Value::Polygon(vec![vec![0]]),
+                Value::Polygon(vec![vec![0]]),
+                Value::Polygon(vec![vec![-1]]),
+                Value::Polygon(vec![vec![0]]),
+            ]
+        );
+    }
+
+    ///
+    /// D----C----F
+    /// |    |    |
+    /// A----B----E
+    ///
+    #[test]
+    fn overlapping_rings_are_cut() {
+        println!(
+            "topology overlapping rings ABCDA and BEFCB are cut into BC.CDAB and BEFC.CB"
+        );
+        let abcda = polygon![
+            (x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 1.), (x: 0., y: 1.)
+        ];
+        let befcb = polygon![
+            (x: 1., y: 0.), (x: 2., y: 0.), (x: 2., y: 1.), (x: 1., y: 1.)
+        ];
+        let t =
+            topology(objects(vec![abcda.into(), befcb.clone().into()]), None);
+        assert_eq!(
+            t.arcs,
+            arcs(&[
+                &[[1., 0.], [1., 1.]],
+                &[[1., 1.], [0., 1.], [0., 0.], [1., 0.]],
+                &[[1., 0.], [2., 0.], [2., 1.], [1., 1.]],
+            ])
+        );
+        assert_eq!(
+            values(&t),
+            vec![
+                Value::Polygon(vec![vec![0, 1]]),
+                Value::Polygon(vec![vec![2, -1]]),
+            ]
+        );
+
+        // The first ring is rotated to start at the junction B.
+        assert_eq!(
+            feature::<f64>(&t, &t.objects[0].geometry.value),
+            Geometry::Polygon(polygon![
+                (x: 1., y: 0.), (x: 1., y: 1.), (x: 0., y: 1.), (x: 0., y: 0.)
+            ])
+        );
+        assert_eq!(
+            feature::<f64>(&t, &t.objects[1].geometry.value),
+            Geometry::Polygon(befcb)
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn round_trips_through_feature() {
+        let input: Vec<Geometry> = vec![
+            point!(x: 5., y: 5.).into(),
+            Geometry::MultiPoint(vec![point!(x: 1., y: 2.)].into()),
+            line_string![(x: 0., y: 0.), (x: 1., y: 0.), (x: 1., y: 2.)].into(),
+            Geometry::MultiLineString(MultiLineString(
+                vec![
+                    line_string![(x: 1., y: 0.), (x: 4., y: 0.)],
+                    line_string![(x: 9., y: 9.), (x: 8., y: 8.)],
+                ]
+                ),
+            ),
+            polygon!(
+                exterior: [
+                    (x: 0., y: 0.), (x: 10., y: 0.), (x: 10., y: 10.),
+                    (x: 0., y: 10.)
+                ],
+                interiors: [[(x: 2., y: 2.), (x: 2., y: 3.), (x: 3., y: 3.)]],
+            )
+            .into(),
+            Geometry::GeometryCollection(
+                vec![Geometry::MultiPolygon(
+                    vec![
+                        polygon![(x: 2., y: 2.), (x: 3., y: 3.), (x: 2., y: 3.)],
+                        polygon![(x: 6., y: 6.), (x: 7., y: 6.), (x: 7., y: 7.)],
+                    ]
+                    .into(),
+                )]
+                .into(),
+            ),
+        ];
+        let t = topology(objects(input.clone()), None);
+
+        assert_eq!(t.bbox, Some(vec![0., 0., 10., 10.]));
+        assert_eq!(t.transform, None);
+        // The hole and the first polygon of the collection share a ring.
+        assert_eq!(
+            t.objects[5].geometry.value,
+            Value::GeometryCollection(vec![topojson::Geometry::new(
+                Value::MultiPolygon(vec![vec![vec![-6]], vec![vec![6]]])
+            )])
+        );
+        for (o, g) in t.objects.iter().zip(input) {
+            assert_eq!(feature::<f64>(&t, &o.geometry.value), g);
+        }
+
+        let t = topology(
+            objects(vec![
+                Line::new(Coord { x: 5., y: 5. }, Coord { x: 6., y: 6. })
+                    .into(),
+                Rect::new(Coord { x: 0., y: 0. }, Coord { x: 1., y: 1. })
+                    .into(),
+            ]),
+            None,
+        );
+        assert_eq!(
+            feature::<f64>(&t, &t.objects[0].geometry.value),
+            line_string![(x: 5., y: 5.), (x: 6., y: 6.)].into()
+        );
+        assert_eq!(
+            feature::<f64>(&t, &t.objects[1].geometry.value),
+            Rect::new(Coord { x: 0., y: 0. }, Coord { x: 1., y: 1. })
+                .to_polygon()
+                .into()
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn quantizes_and_delta_encodes() {
+        let t = topology(
+            objects(vec![
+                point!(x: 5., y: 5.).into(),
+                line_string![
+                    (x: 0., y: 0.), (x: 1., y: 1.), (x: 10., y: 10.),
+                    (x: 10., y: 0.)
+                ]
+                .into(),
+            ]),
+            Some(3),
+        );
+        assert_eq!(
+            t.transform,
+            Some(TransformParams {
+                scale: [5., 5.],
+                translate: [0., 0.],
+            })
+        );
+        assert_eq!(t.objects[0].geometry.value, Value::Point(vec![1., 1.]));
+        // The coincident point [0, 0] is removed.
+        assert_eq!(t.arcs, arcs(&[&[[0., 0.], [2., 2.], [0., -2.]]]));
+        assert_eq!(
+            feature::<f64>(&t, &t.objects[1].geometry.value),
+            line_string![(x: 0., y: 0.), (x: 10., y: 10.), (x: 10., y: 0.)]
+                .into()
+        );
+
+        // An empty input has no bbox, and so is not quantized.
+        let t = topology(vec![], Some(1_000));
+        assert_eq!(t.bbox, None);
+        assert_eq!(t.transform, None);
+        assert!(t.arcs.is_empty());
+    }
+}
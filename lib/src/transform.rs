@@ -1,76 +1,163 @@
 use topojson::TransformParams;
 
-pub type Transform = Box<dyn FnMut(&[f64], usize) -> Vec<f64>>;
-/// Return a transform function base on the transform paramters.
+/// Converts the quantized positions of a topology into absolute positions.
 ///
-/// When the transform parameters are undefined return an identity transform.
-pub fn gen_transform(tp: &Option<TransformParams>) -> Transform {
-    match tp {
-        None => {
-            Box::new(|input: &[f64], _: usize| -> Vec<f64> { Vec::from(input) })
+/// A plain `Copy` value, so it is `Send + Sync` and can be shared by worker
+/// threads. Arcs are decoded with a [`Decoder`], which holds the running
+/// position. Nothing is allocated, positions are returned by value or
+/// written into buffers provided by the caller.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    kx: f64,
+    ky: f64,
+    dx: f64,
+    dy: f64,
+    /// Without a transform, arcs hold absolute positions, not deltas.
+    delta: bool,
+}
+
+impl Transform {
+    /// Return a transform based on the transform paramters.
+    ///
+    /// When the transform parameters are undefined return an identity
+    /// transform.
+    pub const fn new(tp: Option<&TransformParams>) -> Self {
+        match tp {
+            None => Self {
+                kx: 1_f64,
+                ky: 1_f64,
+                dx: 0_f64,
+                dy: 0_f64,
+                delta: false,
+            },
+            Some(tp) => Self {
+                kx: tp.scale[0],
+                ky: tp.scale[1],
+                dx: tp.translate[0],
+                dy: tp.translate[1],
+                delta: true,
+            },
+        }
+    }
+
+    /// Transform a single position, as found in `Point` geometries.
+    #[inline]
+    // `mul_add()` rounds differently from javascript.
+    #[allow(clippy::suboptimal_flops)]
+    pub fn point(&self, p: &[f64]) -> [f64; 2] {
+        if self.delta {
+            [p[0] * self.kx + self.dx, p[1] * self.ky + self.dy]
+        } else {
+            [p[0], p[1]]
         }
-        Some(tp) => {
-            let mut x0: f64 = 0_f64;
-            let mut y0: f64 = 0_f64;
-            let kx: f64 = tp.scale[0];
-            let ky: f64 = tp.scale[1];
-            let dx: f64 = tp.translate[0];
-            let dy: f64 = tp.translate[1];
-            Box::new(move |input: &[f64], i: usize| -> Vec<f64> {
-                if i == 0 {
-                    x0 = 0_f64;
-                    y0 = 0_f64;
-                }
-                let mut j = 2;
-                let n = input.len();
-                let mut output = Vec::with_capacity(n);
-                x0 += input[0];
-                output.push(x0 * kx + dx);
-                y0 += input[1];
-                output.push(y0 * ky + dy);
-
-                // Copy over all remaining point in the input vector?
-                while j < n {
-                    output.push(input[j]);
-                    j += 1;
-                }
-                output
-            })
+    }
+
+    /// Returns a decoder positioned at the start of an arc.
+    pub const fn decoder(&self) -> Decoder {
+        Decoder {
+            t: *self,
+            x: 0_f64,
+            y: 0_f64,
+        }
+    }
+
+    /// Decodes the arc, appending its positions to `out`.
+    pub fn arc<P>(&self, arc: &[P], out: &mut Vec<(f64, f64)>)
+    where
+        P: AsRef<[f64]>,
+    {
+        out.reserve(arc.len());
+        if self.delta {
+            let mut d = self.decoder();
+            out.extend(
+                arc.iter().map(|p| <(f64, f64)>::from(d.decode(p.as_ref()))),
+            );
+        } else {
+            out.extend(arc.iter().map(|p| {
+                let p = p.as_ref();
+                (p[0], p[1])
+            }));
         }
     }
 }
 
+/// The state needed to decode the positions of one delta-encoded arc.
+#[derive(Clone, Copy, Debug)]
+pub struct Decoder {
+    t: Transform,
+    x: f64,
+    y: f64,
+}
+
+impl Decoder {
+    /// Decodes the next position of the arc.
+    #[inline]
+    pub fn decode(&mut self, p: &[f64]) -> [f64; 2] {
+        if self.t.delta {
+            self.x += p[0];
+            self.y += p[1];
+            self.t.point(&[self.x, self.y])
+        } else {
+            [p[0], p[1]]
+        }
+    }
+
+    /// Decodes the next position of the arc into `out`, which is cleared
+    /// first.
+    ///
+    /// Dimensions beyond the first two are copied over untouched.
+    pub fn decode_into(&mut self, p: &[f64], out: &mut Vec<f64>) {
+        out.clear();
+        out.extend_from_slice(&self.decode(p));
+        out.extend_from_slice(&p[2..]);
+    }
+}
+
 #[cfg(test)]
 mod transform_tests {
     use pretty_assertions::assert_eq;
 
     use super::*;
 
-    // tape("topojson.transform(topology) returns the identity function if transform is undefined", function(test) {
-    //   var transform = topojson.transform(null),
-    //       point;
-    //   test.equal(transform(point = {}), point);
-    //   test.end();
-    // });
-    // #[test]
-    // fn transform_returns_the_identity_function() {
-    //     println!(
-    //         "topojson.transform(topology) returns the identity function if transform is undefined"
-    //     );
-    //     let point;
-    //     let transform = transform(None);
-    //     assert_eq!(transform, None);
-    // }
-
-    #[test]
-    fn returns_a_point_transform_function() {
-        println!("topojson.transform(topology) returns a point-transform function if transform is defined");
-        let mut transform = gen_transform(&Some(TransformParams {
+    fn transform() -> Transform {
+        Transform::new(Some(&TransformParams {
             scale: [2_f64, 3_f64],
             translate: [4_f64, 5_f64],
-        }));
+        }))
+    }
+
+    #[test]
+    fn returns_the_identity_function_if_transform_is_undefined() {
+        println!(
+            "topojson.transform(topology) returns the identity function if transform is undefined"
+        );
+        let transform = Transform::new(None);
+        assert_eq!(
+            transform.point(&[6.5_f64, 7.25_f64]).to_vec(),
+            vec![6.5_f64, 7.25_f64]
+        );
 
-        assert_eq!(transform(&[6_f64, 7_f64], 0), vec![16_f64, 26_f64]);
+        let mut decoder = transform.decoder();
+        assert_eq!(
+            decoder.decode(&[1_f64, 2_f64]).to_vec(),
+            vec![1_f64, 2_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[3_f64, 4_f64]).to_vec(),
+            vec![3_f64, 4_f64]
+        );
+    }
+
+    #[test]
+    fn returns_a_point_transform_function() {
+        println!(
+            "topojson.transform(topology) returns a point-transform function if transform is defined"
+        );
+        let transform = transform();
+        assert_eq!(
+            transform.point(&[6_f64, 7_f64]).to_vec(),
+            vec![16_f64, 26_f64]
+        );
     }
 
     // This test does not need to be ported because rust handles mutability differently.
@@ -79,7 +166,7 @@ mod transform_tests {
     // tape("transform(point) returns a new point", function(test) {
     //   var transform = topojson.transform({scale: [2, 3], translate: [4, 5]}),
     //       point = [6, 7];
-    //   test.deepEqual(transform(point), [16, 26]);
+    //   test.deepEqual(transform(point), vec![16, 26]);
     //   test.deepEqual(point, [6, 7]);
     //   test.end();
     // });
@@ -87,55 +174,95 @@ mod transform_tests {
     #[test]
     fn preserves_extra_dimensions() {
         println!("transform(point) preserves extra dimensions");
-        let mut transform = gen_transform(&Some(TransformParams {
-            scale: [2_f64, 3_f64],
-            translate: [4_f64, 5_f64],
-        }));
-        assert_eq!(
-            transform(&[6_f64, 7_f64, 42_f64], 0),
-            [16_f64, 26_f64, 42_f64]
-        );
+        let mut out = vec![];
+        transform()
+            .decoder()
+            .decode_into(&[6_f64, 7_f64, 42_f64], &mut out);
+        assert_eq!(out, vec![16_f64, 26_f64, 42_f64]);
     }
 
     #[test]
     fn transforms_individual_points() {
         println!("transform(point) transforms individual points");
-        let mut transform = gen_transform(&Some(TransformParams {
-            scale: [2_f64, 3_f64],
-            translate: [4_f64, 5_f64],
-        }));
-        assert_eq!(transform(&[1_f64, 2_f64], 0), vec![6_f64, 11_f64]);
-        assert_eq!(transform(&[3_f64, 4_f64], 0), vec![10_f64, 17_f64]);
-        assert_eq!(transform(&[5_f64, 6_f64], 0), vec![14_f64, 23_f64]);
+        let transform = transform();
+        assert_eq!(
+            transform.point(&[1_f64, 2_f64]).to_vec(),
+            vec![6_f64, 11_f64]
+        );
+        assert_eq!(
+            transform.point(&[3_f64, 4_f64]).to_vec(),
+            vec![10_f64, 17_f64]
+        );
+        assert_eq!(
+            transform.point(&[5_f64, 6_f64]).to_vec(),
+            vec![14_f64, 23_f64]
+        );
     }
 
     #[test]
     fn transforms_delta_encoded_arcs() {
         println!("transform(point, index) transforms delta-encoded arcs");
-        let mut transform = gen_transform(&Some(TransformParams {
-            scale: [2_f64, 3_f64],
-            translate: [4_f64, 5_f64],
-        }));
-        assert_eq!(transform(&[1_f64, 2_f64], 0), vec![6_f64, 11_f64]);
-        assert_eq!(transform(&[3_f64, 4_f64], 1), vec![12_f64, 23_f64]);
-        assert_eq!(transform(&[5_f64, 6_f64], 2), vec![22_f64, 41_f64]);
-        assert_eq!(transform(&[1_f64, 2_f64], 3), vec![24_f64, 47_f64]);
-        assert_eq!(transform(&[3_f64, 4_f64], 4), vec![30_f64, 59_f64]);
-        assert_eq!(transform(&[5_f64, 6_f64], 5), vec![40_f64, 77_f64]);
+        let mut decoder = transform().decoder();
+        assert_eq!(
+            decoder.decode(&[1_f64, 2_f64]).to_vec(),
+            vec![6_f64, 11_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[3_f64, 4_f64]).to_vec(),
+            vec![12_f64, 23_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[5_f64, 6_f64]).to_vec(),
+            vec![22_f64, 41_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[1_f64, 2_f64]).to_vec(),
+            vec![24_f64, 47_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[3_f64, 4_f64]).to_vec(),
+            vec![30_f64, 59_f64]
+        );
+        assert_eq!(
+            decoder.decode(&[5_f64, 6_f64]).to_vec(),
+            vec![40_f64, 77_f64]
+        );
     }
 
     #[test]
     fn transforms_mutliple_delta_encoded_arcs() {
         println!("transform(point, index) transforms delta-encoded arcs");
-        let mut transform = gen_transform(&Some(TransformParams {
-            scale: [2_f64, 3_f64],
-            translate: [4_f64, 5_f64],
-        }));
-        assert_eq!(transform(&[1_f64, 2_f64], 0), vec![6_f64, 11_f64]);
-        assert_eq!(transform(&[3_f64, 4_f64], 1), vec![12_f64, 23_f64]);
-        assert_eq!(transform(&[5_f64, 6_f64], 2), vec![22_f64, 41_f64]);
-        assert_eq!(transform(&[1_f64, 2_f64], 0), vec![6_f64, 11_f64]);
-        assert_eq!(transform(&[3_f64, 4_f64], 1), vec![12_f64, 23_f64]);
-        assert_eq!(transform(&[5_f64, 6_f64], 2), vec![22_f64, 41_f64]);
+        let transform = transform();
+        let arc = [vec![1_f64, 2_f64], vec![3_f64, 4_f64], vec![5_f64, 6_f64]];
+        let mut out = vec![];
+        transform.arc(&arc, &mut out);
+        transform.arc(&arc, &mut out);
+        assert_eq!(
+            out,
+            vec![
+                (6_f64, 11_f64),
+                (12_f64, 23_f64),
+                (22_f64, 41_f64),
+                (6_f64, 11_f64),
+                (12_f64, 23_f64),
+                (22_f64, 41_f64),
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn can_be_shared_across_threads() {
+        const fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Transform>();
+        assert_send_sync::<Decoder>();
+
+        let transform = transform();
+        let points = std::thread::scope(|s| {
+            let a = s.spawn(|| transform.point(&[1_f64, 2_f64]));
+            let b = s.spawn(|| transform.point(&[3_f64, 4_f64]));
+            [a.join().unwrap(), b.join().unwrap()]
+        });
+        assert_eq!(points.to_vec(), vec![[6_f64, 11_f64], [10_f64, 17_f64]]);
     }
 }
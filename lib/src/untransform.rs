@@ -0,0 +1,226 @@
+use topojson::TransformParams;
+
+/// Converts absolute positions into the quantized positions of a topology.
+///
+/// The inverse of the decoding applied by `feature()`, use it to write edited
+/// coordinates back into a quantized topology. A plain `Copy` value, so it is
+/// `Send + Sync`. Arcs are delta-quantized with an [`Encoder`], which holds the
+/// previous position.
+///
+/// Positions are rounded to the nearest integer, any extra dimensions are
+/// copied over untouched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Untransform {
+    kx: f64,
+    ky: f64,
+    dx: f64,
+    dy: f64,
+    /// Without a transform, arcs hold absolute positions, not deltas.
+    delta: bool,
+}
+
+impl Untransform {
+    /// Return an untransform based on the transform parameters.
+    ///
+    /// When the transform parameters are undefined return an identity
+    /// untransform.
+    #[must_use]
+    pub const fn new(tp: Option<&TransformParams>) -> Self {
+        match tp {
+            None => Self {
+                kx: 1_f64,
+                ky: 1_f64,
+                dx: 0_f64,
+                dy: 0_f64,
+                delta: false,
+            },
+            Some(tp) => Self {
+                kx: tp.scale[0],
+                ky: tp.scale[1],
+                dx: tp.translate[0],
+                dy: tp.translate[1],
+                delta: true,
+            },
+        }
+    }
+
+    /// Quantize a single position, as found in `Point` geometries.
+    #[inline]
+    #[must_use]
+    pub fn point(&self, p: &[f64]) -> [f64; 2] {
+        if self.delta {
+            [
+                round((p[0] - self.dx) / self.kx),
+                round((p[1] - self.dy) / self.ky),
+            ]
+        } else {
+            [p[0], p[1]]
+        }
+    }
+
+    /// Returns an encoder positioned at the start of an arc.
+    #[must_use]
+    pub const fn encoder(&self) -> Encoder {
+        Encoder {
+            t: *self,
+            x: 0_f64,
+            y: 0_f64,
+        }
+    }
+}
+
+/// The state needed to delta-encode the positions of one arc.
+///
+/// The first position of an arc is returned as an absolute quantized
+/// position, each following position as the delta from the one before.
+#[derive(Clone, Copy, Debug)]
+pub struct Encoder {
+    t: Untransform,
+    x: f64,
+    y: f64,
+}
+
+impl Encoder {
+    /// Encodes the next position of the arc.
+    #[inline]
+    pub fn encode(&mut self, p: &[f64]) -> [f64; 2] {
+        if self.t.delta {
+            let [x, y] = self.t.point(p);
+            let delta = [x - self.x, y - self.y];
+            self.x = x;
+            self.y = y;
+            delta
+        } else {
+            [p[0], p[1]]
+        }
+    }
+
+    /// Encodes the next position of the arc into `out`, which is cleared
+    /// first.
+    ///
+    /// Dimensions beyond the first two are copied over untouched.
+    pub fn encode_into(&mut self, p: &[f64], out: &mut Vec<f64>) {
+        out.clear();
+        out.extend_from_slice(&self.encode(p));
+        out.extend_from_slice(&p[2..]);
+    }
+}
+
+/// Rounds half-way cases towards positive infinity, as javascript's
+/// `Math.round()` does.
+#[inline]
+fn round(x: f64) -> f64 {
+    (x + 0.5_f64).floor()
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod untransform_tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::transform::Transform;
+
+    fn transform_params() -> TransformParams {
+        TransformParams {
+            scale: [2_f64, 3_f64],
+            translate: [4_f64, 5_f64],
+        }
+    }
+
+    #[test]
+    fn returns_the_identity_function_if_transform_is_undefined() {
+        println!(
+            "topojson.untransform(topology) returns the identity function if transform is undefined"
+        );
+        let untransform = Untransform::new(None);
+        assert_eq!(
+            untransform.point(&[6.5_f64, 7.25_f64]),
+            [6.5_f64, 7.25_f64]
+        );
+        let mut encoder = untransform.encoder();
+        assert_eq!(encoder.encode(&[6.5_f64, 7.25_f64]), [6.5_f64, 7.25_f64]);
+        assert_eq!(encoder.encode(&[6.5_f64, 7.25_f64]), [6.5_f64, 7.25_f64]);
+    }
+
+    #[test]
+    fn returns_a_point_transform_function() {
+        println!(
+            "topojson.untransform(topology) returns a point-transform function if transform is defined"
+        );
+        let untransform = Untransform::new(Some(&transform_params()));
+        assert_eq!(untransform.point(&[16_f64, 26_f64]), [6_f64, 7_f64]);
+    }
+
+    #[test]
+    fn preserves_extra_dimensions() {
+        println!("untransform(point) preserves extra dimensions");
+        let untransform = Untransform::new(Some(&transform_params()));
+        let mut out = vec![];
+        untransform
+            .encoder()
+            .encode_into(&[16_f64, 26_f64, 42_f64], &mut out);
+        assert_eq!(out, vec![6_f64, 7_f64, 42_f64]);
+        untransform
+            .encoder()
+            .encode_into(&[16_f64, 26_f64, 42.5_f64, -1_f64], &mut out);
+        assert_eq!(out, vec![6_f64, 7_f64, 42.5_f64, -1_f64]);
+    }
+
+    #[test]
+    fn untransforms_individual_points() {
+        println!("untransform(point) untransforms individual points");
+        let untransform = Untransform::new(Some(&transform_params()));
+        assert_eq!(untransform.point(&[6_f64, 11_f64]), [1_f64, 2_f64]);
+        assert_eq!(untransform.point(&[10_f64, 17_f64]), [3_f64, 4_f64]);
+        assert_eq!(untransform.point(&[14_f64, 23_f64]), [5_f64, 6_f64]);
+    }
+
+    #[test]
+    fn untransforms_delta_encoded_arcs() {
+        println!("untransform(point, index) untransforms delta-quantized arcs");
+        let mut encoder = Untransform::new(Some(&transform_params())).encoder();
+        assert_eq!(encoder.encode(&[6_f64, 11_f64]), [1_f64, 2_f64]);
+        assert_eq!(encoder.encode(&[12_f64, 23_f64]), [3_f64, 4_f64]);
+        assert_eq!(encoder.encode(&[22_f64, 41_f64]), [5_f64, 6_f64]);
+        assert_eq!(encoder.encode(&[24_f64, 47_f64]), [1_f64, 2_f64]);
+        assert_eq!(encoder.encode(&[30_f64, 59_f64]), [3_f64, 4_f64]);
+        assert_eq!(encoder.encode(&[40_f64, 77_f64]), [5_f64, 6_f64]);
+    }
+
+    #[test]
+    fn untransforms_multiple_delta_encoded_arcs() {
+        println!(
+            "untransform(point, index) untransforms multiple delta-quantized arcs"
+        );
+        let untransform = Untransform::new(Some(&transform_params()));
+        let mut encoder = untransform.encoder();
+        assert_eq!(encoder.encode(&[6_f64, 11_f64]), [1_f64, 2_f64]);
+        assert_eq!(encoder.encode(&[12_f64, 23_f64]), [3_f64, 4_f64]);
+        assert_eq!(encoder.encode(&[22_f64, 41_f64]), [5_f64, 6_f64]);
+        let mut encoder = untransform.encoder();
+        assert_eq!(encoder.encode(&[6_f64, 11_f64]), [1_f64, 2_f64]);
+        assert_eq!(encoder.encode(&[12_f64, 23_f64]), [3_f64, 4_f64]);
+        assert_eq!(encoder.encode(&[22_f64, 41_f64]), [5_f64, 6_f64]);
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn is_the_inverse_of_transform() {
+        let tp = transform_params();
+        let mut decoder = Transform::new(Some(&tp)).decoder();
+        let mut encoder = Untransform::new(Some(&tp)).encoder();
+        let arc = [
+            vec![1_f64, 2_f64, 100_f64],
+            vec![3_f64, 4_f64, 101_f64],
+            vec![-2_f64, 1_f64, 102_f64],
+        ];
+        let mut absolute = vec![];
+        let mut quantized = vec![];
+        for p in &arc {
+            decoder.decode_into(p, &mut absolute);
+            encoder.encode_into(&absolute, &mut quantized);
+            assert_eq!(&quantized, p);
+        }
+    }
+}
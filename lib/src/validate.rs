@@ -0,0 +1,494 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use topojson::{ArcIndexes, Topology, Value};
+
+use crate::transform::Transform;
+use crate::translate;
+
+/// The ways in which a topology can violate the `TopoJSON` specification,
+/// or otherwise be unusable by `stitch()` and `merge()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The transform scale is zero, or the transform is not finite.
+    InvalidTransform,
+    /// Several objects share the same name.
+    DuplicateName,
+    /// An arc has fewer than two positions.
+    ShortArc {
+        /// The index of the arc in the topology.
+        arc: usize,
+    },
+    /// A position has fewer than two coordinates, or a coordinate is not
+    /// finite.
+    InvalidPosition {
+        /// The index of the arc in the topology, `None` for points.
+        arc: Option<usize>,
+    },
+    /// A quantized arc contains a delta which is not an integer.
+    NonIntegerDelta {
+        /// The index of the arc in the topology.
+        arc: usize,
+        /// The index of the position within the arc.
+        position: usize,
+    },
+    /// An arc index refers to an arc which does not exist.
+    ArcIndexOutOfRange {
+        /// The arc index, as found in the geometry.
+        index: i32,
+    },
+    /// A line or a ring does not reference any arcs.
+    EmptyArcIndexes,
+    /// The end of an arc is not the start of the next arc in the line.
+    DisconnectedArcs {
+        /// The index of the arc index, within the line, which does not
+        /// connect to the one before it.
+        at: usize,
+    },
+    /// The last position of a ring is not its first position.
+    RingNotClosed,
+    /// A ring has fewer than four positions.
+    ShortRing,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidTransform => write!(f, "invalid transform"),
+            Self::DuplicateName => write!(f, "duplicate object name"),
+            Self::ShortArc { arc } => {
+                write!(f, "arc {arc} has fewer than two positions")
+            }
+            Self::InvalidPosition { arc: Some(arc) } => {
+                write!(f, "arc {arc} has an invalid position")
+            }
+            Self::InvalidPosition { arc: None } => {
+                write!(f, "invalid position")
+            }
+            Self::NonIntegerDelta { arc, position } => write!(
+                f,
+                "arc {arc} has a non-integer delta at position {position}"
+            ),
+            Self::ArcIndexOutOfRange { index } => {
+                write!(f, "arc index {index} is out of range")
+            }
+            Self::EmptyArcIndexes => {
+                write!(f, "a line or ring does not reference any arcs")
+            }
+            Self::DisconnectedArcs { at } => {
+                write!(f, "arc index {at} does not connect to the previous arc")
+            }
+            Self::RingNotClosed => write!(f, "ring is not closed"),
+            Self::ShortRing => write!(f, "ring has fewer than four positions"),
+        }
+    }
+}
+
+/// A violation found by [`validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Issue {
+    /// The name of the object, `None` for issues with the transform, or with
+    /// arcs which no object references.
+    pub object: Option<String>,
+    /// The index of each nested geometry within its collection, followed by
+    /// the index of the line, polygon or ring within the geometry.
+    pub path: Vec<usize>,
+    /// What is wrong.
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(object) = &self.object {
+            write!(f, "object \"{object}\" at {:?}: ", self.path)?;
+        }
+        write!(f, "{}", self.kind)
+    }
+}
+
+/// Checks the topology against the `TopoJSON` specification.
+///
+/// Returns every issue found, an empty list means the topology is valid.
+/// An issue with an arc is reported for each line or ring which references
+/// the arc, and only reported against the arc itself when nothing
+/// references it.
+#[must_use]
+pub fn validate(topology: &Topology) -> Vec<Issue> {
+    let mut v = Validator {
+        ends: Vec::with_capacity(topology.arcs.len()),
+        arc_issues: vec![vec![]; topology.arcs.len()],
+        referenced: vec![false; topology.arcs.len()],
+        issues: vec![],
+        object: None,
+        path: vec![],
+    };
+
+    let valid_transform = topology.transform.as_ref().is_none_or(|tp| {
+        tp.scale.iter().chain(&tp.translate).all(|x| x.is_finite())
+            && !tp.scale.contains(&0_f64)
+    });
+    if !valid_transform {
+        v.report(IssueKind::InvalidTransform);
+    }
+
+    let t = Transform::new(topology.transform.as_ref());
+    for (arc, positions) in topology.arcs.iter().enumerate() {
+        let arc_issues = &mut v.arc_issues[arc];
+        if positions.len() < 2 {
+            arc_issues.push(IssueKind::ShortArc { arc });
+        }
+        if !positions.iter().all(|p| is_position(p)) {
+            arc_issues.push(IssueKind::InvalidPosition { arc: Some(arc) });
+            v.ends.push(None);
+            continue;
+        }
+        if topology.transform.is_some() {
+            for (position, p) in positions.iter().enumerate() {
+                if p[0].fract() != 0_f64 || p[1].fract() != 0_f64 {
+                    arc_issues
+                        .push(IssueKind::NonIntegerDelta { arc, position });
+                    break;
+                }
+            }
+        }
+
+        let mut d = t.decoder();
+        let mut decoded = positions.iter().map(|p| d.decode(p));
+        let first = decoded.next();
+        let last = decoded.last().or(first);
+        v.ends.push(
+            first
+                .zip(last)
+                .filter(|_| valid_transform)
+                .map(|(first, last)| (first, last, positions.len())),
+        );
+    }
+
+    let mut names = BTreeSet::new();
+    for o in &topology.objects {
+        v.object = Some(&o.name);
+        if !names.insert(&o.name) {
+            v.report(IssueKind::DuplicateName);
+        }
+        v.geometry(&o.geometry.value);
+    }
+
+    v.object = None;
+    for arc in 0..topology.arcs.len() {
+        if !v.referenced[arc] {
+            for kind in std::mem::take(&mut v.arc_issues[arc]) {
+                v.report(kind);
+            }
+        }
+    }
+
+    v.issues
+}
+
+fn is_position(p: &[f64]) -> bool {
+    p.len() >= 2 && p.iter().all(|x| x.is_finite())
+}
+
+/// The decoded end points of a line, and its number of positions.
+type Ends = ([f64; 2], [f64; 2], usize);
+
+struct Validator<'a> {
+    // `None` when the arc cannot be decoded.
+    ends: Vec<Option<Ends>>,
+    // The issues with each arc, reported against the lines which use it.
+    arc_issues: Vec<Vec<IssueKind>>,
+    referenced: Vec<bool>,
+    issues: Vec<Issue>,
+    object: Option<&'a str>,
+    path: Vec<usize>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, kind: IssueKind) {
+        self.issues.push(Issue {
+            object: self.object.map(ToString::to_string),
+            path: self.path.clone(),
+            kind,
+        });
+    }
+
+    fn point(&mut self, p: &[f64]) {
+        if !is_position(p) {
+            self.report(IssueKind::InvalidPosition { arc: None });
+        }
+    }
+
+    /// Joins the arcs, returns `None` if the line is unusable.
+    ///
+    /// Like `stitch()`, arcs only connect when the positions are identical.
+    #[allow(clippy::float_cmp)]
+    fn line(&mut self, arcs: &[i32]) -> Option<Ends> {
+        if arcs.is_empty() {
+            self.report(IssueKind::EmptyArcIndexes);
+            return None;
+        }
+
+        let mut joined: Option<Ends> = None;
+        let mut usable = true;
+        for (at, i) in arcs.iter().enumerate() {
+            let index = translate(*i);
+            let Some(&ends) = self.ends.get(index) else {
+                self.report(IssueKind::ArcIndexOutOfRange { index: *i });
+                usable = false;
+                continue;
+            };
+            self.referenced[index] = true;
+            for kind in self.arc_issues[index].clone() {
+                self.report(kind);
+            }
+            let Some((start, end, len)) = ends else {
+                usable = false;
+                continue;
+            };
+            let (start, end) = if *i < 0 { (end, start) } else { (start, end) };
+            joined = match joined {
+                None => Some((start, end, len)),
+                Some((first, last, n)) => {
+                    if last != start {
+                        self.report(IssueKind::DisconnectedArcs { at });
+                        usable = false;
+                    }
+                    // The shared position is counted once.
+                    Some((first, end, n + len - 1))
+                }
+            };
+        }
+        joined.filter(|_| usable)
+    }
+
+    #[allow(clippy::float_cmp)]
+    fn ring(&mut self, arcs: &[i32]) {
+        if let Some((first, last, n)) = self.line(arcs) {
+            if first != last {
+                self.report(IssueKind::RingNotClosed);
+            } else if n < 4 {
+                self.report(IssueKind::ShortRing);
+            }
+        }
+    }
+
+    fn lines(&mut self, lines: &[ArcIndexes], ring: bool) {
+        for (i, arcs) in lines.iter().enumerate() {
+            self.path.push(i);
+            if ring {
+                self.ring(arcs);
+            } else {
+                self.line(arcs);
+            }
+            self.path.pop();
+        }
+    }
+
+    fn geometry(&mut self, o: &Value) {
+        match o {
+            Value::GeometryCollection(gc) => {
+                for (i, g) in gc.iter().enumerate() {
+                    self.path.push(i);
+                    self.geometry(&g.value);
+                    self.path.pop();
+                }
+            }
+            Value::Point(p) => self.point(p),
+            Value::MultiPoint(mp) => {
+                for p in mp {
+                    self.point(p);
+                }
+            }
+            Value::LineString(arcs) => {
+                self.line(arcs);
+            }
+            Value::MultiLineString(lines) => self.lines(lines, false),
+            Value::Polygon(rings) => self.lines(rings, true),
+            Value::MultiPolygon(polygons) => {
+                for (i, rings) in polygons.iter().enumerate() {
+                    self.path.push(i);
+                    self.lines(rings, true);
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use std::fs::File;
+
+    use pretty_assertions::assert_eq;
+    use topojson::{Geometry, NamedGeometry, TransformParams};
+
+    use super::*;
+
+    fn read(path: &str) -> Topology {
+        let file = File::open(path).expect("Could not load json file.");
+        serde_json::from_reader(file).expect("Did not parse correctly.")
+    }
+
+    fn issue(object: Option<&str>, path: Vec<usize>, kind: IssueKind) -> Issue {
+        Issue {
+            object: object.map(ToString::to_string),
+            path,
+            kind,
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn accepts_valid_topologies() {
+        for path in [
+            "./tests/topojson/polygon.json",
+            "./tests/topojson/polygon-q1e4.json",
+            "./tests/topojson/polygon-mercator-q1e5.json",
+            "./tests/topojson/properties.json",
+            "./tests/topojson/points-q1e5.json",
+        ] {
+            assert_eq!(validate(&read(path)), vec![], "{path}");
+        }
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn reports_arc_and_transform_issues() {
+        let topology = Topology {
+            arcs: vec![
+                vec![vec![0_f64, 0_f64]],
+                vec![vec![0_f64, 0_f64], vec![0.5_f64, 1_f64]],
+                vec![vec![0_f64, 0_f64], vec![f64::NAN, 1_f64]],
+            ],
+            objects: vec![],
+            bbox: None,
+            transform: Some(TransformParams {
+                scale: [1_f64, 0_f64],
+                translate: [0_f64, 0_f64],
+            }),
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            validate(&topology),
+            vec![
+                issue(None, vec![], IssueKind::InvalidTransform),
+                issue(None, vec![], IssueKind::ShortArc { arc: 0 }),
+                issue(
+                    None,
+                    vec![],
+                    IssueKind::NonIntegerDelta {
+                        arc: 1,
+                        position: 1
+                    }
+                ),
+                issue(
+                    None,
+                    vec![],
+                    IssueKind::InvalidPosition { arc: Some(2) }
+                ),
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn reports_arc_issues_for_each_referencing_line() {
+        let topology = Topology {
+            arcs: vec![
+                vec![vec![0_f64, 0_f64]],
+                vec![vec![0_f64, 0_f64], vec![1_f64, 1_f64]],
+                vec![vec![5_f64, 5_f64]],
+            ],
+            objects: vec![
+                NamedGeometry {
+                    name: "a".to_string(),
+                    geometry: Geometry::new(Value::LineString(vec![0])),
+                },
+                NamedGeometry {
+                    name: "b".to_string(),
+                    geometry: Geometry::new(Value::GeometryCollection(vec![
+                        Geometry::new(Value::LineString(vec![1])),
+                        Geometry::new(Value::MultiLineString(vec![
+                            vec![1],
+                            vec![-1],
+                        ])),
+                    ])),
+                },
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        assert_eq!(
+            validate(&topology),
+            vec![
+                issue(Some("a"), vec![], IssueKind::ShortArc { arc: 0 }),
+                issue(Some("b"), vec![1, 1], IssueKind::ShortArc { arc: 0 }),
+                issue(None, vec![], IssueKind::ShortArc { arc: 2 }),
+            ]
+        );
+    }
+
+    // There is no equivalent test in the javascript version.
+    #[test]
+    fn reports_object_issues_with_a_path() {
+        let topology = Topology {
+            arcs: vec![
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                    vec![1_f64, 1_f64],
+                ],
+                vec![vec![1_f64, 1_f64], vec![0_f64, 0_f64]],
+                vec![vec![2_f64, 2_f64], vec![3_f64, 3_f64]],
+                vec![
+                    vec![0_f64, 0_f64],
+                    vec![1_f64, 0_f64],
+                    vec![0_f64, 0_f64],
+                ],
+            ],
+            objects: vec![
+                NamedGeometry {
+                    name: "a".to_string(),
+                    geometry: Geometry::new(Value::GeometryCollection(vec![
+                        Geometry::new(Value::Polygon(vec![vec![0, 1]])),
+                        Geometry::new(Value::MultiPolygon(vec![
+                            vec![vec![0, 1]],
+                            vec![vec![0, 1], vec![0], vec![]],
+                        ])),
+                        Geometry::new(Value::LineString(vec![0, 2, 4, -5])),
+                        Geometry::new(Value::Point(vec![0_f64])),
+                    ])),
+                },
+                NamedGeometry {
+                    name: "a".to_string(),
+                    geometry: Geometry::new(Value::Polygon(vec![vec![3]])),
+                },
+            ],
+            bbox: None,
+            transform: None,
+            foreign_members: None,
+        };
+
+        let a = Some("a");
+        assert_eq!(
+            validate(&topology),
+            vec![
+                issue(a, vec![1, 1, 1], IssueKind::RingNotClosed),
+                issue(a, vec![1, 1, 2], IssueKind::EmptyArcIndexes),
+                issue(a, vec![2], IssueKind::DisconnectedArcs { at: 1 }),
+                issue(a, vec![2], IssueKind::ArcIndexOutOfRange { index: 4 }),
+                issue(a, vec![2], IssueKind::ArcIndexOutOfRange { index: -5 }),
+                issue(a, vec![3], IssueKind::InvalidPosition { arc: None }),
+                issue(a, vec![], IssueKind::DuplicateName),
+                issue(a, vec![0], IssueKind::ShortRing),
+            ]
+        );
+        assert_eq!(
+            validate(&topology)[0].to_string(),
+            "object \"a\" at [1, 1, 1]: ring is not closed"
+        );
+    }
+}
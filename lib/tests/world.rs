@@ -7,10 +7,32 @@ extern crate topojson;
 mod world_test {
 
     use std::fs::File;
+    use std::io::BufReader;
 
     use geo::{Geometry, GeometryCollection};
+    use rust_topojson_client::bbox::bbox;
+    use rust_topojson_client::decoded::DecodedTopology;
+    use rust_topojson_client::feature::feature_flat;
     use rust_topojson_client::feature::feature_from_name;
+    #[cfg(feature = "rayon")]
+    use rust_topojson_client::feature::feature_par;
+    use rust_topojson_client::feature::features_iter;
+    use rust_topojson_client::filter::{
+        filter, filter_attached, filter_weight,
+    };
+    use rust_topojson_client::load::load_objects;
+    #[cfg(feature = "rayon")]
+    use rust_topojson_client::merge::{merge, merge_par};
+    use rust_topojson_client::neighbors::geometry_neighbors;
+    use rust_topojson_client::prune::prune;
+    use rust_topojson_client::simplify::{
+        planar_triangle_area, presimplify, quantile, simplify,
+        spherical_ring_area, spherical_triangle_area,
+    };
+    use rust_topojson_client::topology::topology;
+    use rust_topojson_client::validate::{IssueKind, validate};
     use topojson::Topology;
+    use topojson::Value;
 
     /// Asserts that a MultiPolygon object with 1428 polygons
     /// can be extracted from the "land" object within the map.
@@ -52,4 +74,317 @@ mod world_test {
             }
         };
     }
+
+    /// Asserts that neighbors can be found between the countries, which are
+    /// a mix of Polygon and MultiPolygon objects.
+    #[test]
+    pub fn country_neighbors() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        let countries = topology
+            .objects
+            .iter()
+            .find(|o| o.name == "countries")
+            .expect("countries should be present.");
+        let Value::GeometryCollection(geometries) = &countries.geometry.value
+        else {
+            panic!("countries should be a GeometryCollection");
+        };
+        let neighbors = geometry_neighbors(geometries);
+        assert_eq!(neighbors.len(), 241);
+
+        // Australia has no land borders.
+        assert_eq!(geometries[12].id, Some(serde_json::json!("036")));
+        assert!(neighbors[12].is_empty());
+
+        // France and Spain share a border.
+        assert_eq!(geometries[72].id, Some(serde_json::json!("250")));
+        assert_eq!(geometries[66].id, Some(serde_json::json!("724")));
+        assert!(neighbors[72].contains(&66));
+        assert!(neighbors[66].contains(&72));
+    }
+
+    /// Asserts that the world atlas passes validation.
+    #[test]
+    pub fn world_is_valid() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        assert_eq!(validate(&topology), vec![]);
+    }
+
+    /// Asserts that decoding the arcs up front does not change the result.
+    #[test]
+    pub fn decoded_topology() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+        let decoded = DecodedTopology::new(&topology);
+
+        for name in ["countries", "land"] {
+            assert_eq!(
+                feature_from_name::<f64>(&decoded, name),
+                feature_from_name::<f64>(&topology, name)
+            );
+        }
+        assert_eq!(bbox(&decoded), bbox(&topology));
+    }
+
+    /// Asserts that the parallel versions agree with the sequential ones.
+    #[cfg(feature = "rayon")]
+    #[test]
+    pub fn parallel_countries() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        let countries: Vec<_> = topology
+            .objects
+            .iter()
+            .filter(|o| o.name == "countries")
+            .cloned()
+            .collect();
+        let value = &countries[0].geometry.value;
+
+        assert_eq!(
+            feature_par::<f64>(&topology, value),
+            feature_from_name::<f64>(&topology, "countries").unwrap()
+        );
+        assert_eq!(
+            merge_par::<f64>(&topology, &countries),
+            merge::<f64>(&topology, &countries)
+        );
+    }
+
+    /// Asserts that the countries can be streamed one at a time.
+    #[test]
+    pub fn countries_iter() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        let countries = features_iter::<f64>(&topology, "countries")
+            .expect("countries should be present.");
+        assert_eq!(countries.len(), 241);
+
+        let france = features_iter::<f64>(&topology, "countries")
+            .expect("countries should be present.")
+            .find(|f| f.id == Some(serde_json::json!("250")))
+            .expect("France should be present.");
+        assert!(matches!(france.geometry, Geometry::MultiPolygon(_)));
+        // This file carries ids but no properties.
+        assert!(france.properties.is_empty());
+    }
+
+    /// Asserts that a streamed object decodes as the fully parsed one does.
+    #[test]
+    pub fn streamed_countries() {
+        let path = "./tests/world-atlas/world/50m.json";
+        let file = File::open(path).expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        let file = File::open(path).expect("File should be readable.");
+        let flat = load_objects(BufReader::new(file), &["countries"])
+            .expect("File should be parse as JSON.");
+        assert_eq!(flat.objects.len(), 1);
+        assert_eq!(flat.arcs.len(), topology.arcs.len());
+
+        let countries = flat.object("countries").expect("countries is loaded");
+        assert_eq!(
+            Some(feature_flat::<f64>(&flat, &countries.geometry.value)),
+            feature_from_name::<f64>(&topology, "countries")
+        );
+    }
+
+    /// Asserts that simplifying keeps the arc endpoints, so that shared
+    /// borders stay identical, while dropping most positions.
+    #[test]
+    pub fn simplified_world() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        type TriangleWeight = fn(&[[f64; 2]; 3]) -> f64;
+        let weights: [TriangleWeight; 2] =
+            [planar_triangle_area, spherical_triangle_area];
+        for weight in weights {
+            let presimplified = presimplify(&topology, weight);
+            let simplified =
+                simplify(&presimplified, quantile(&presimplified, 0.1_f64));
+
+            let positions =
+                |t: &Topology| t.arcs.iter().map(Vec::len).sum::<usize>();
+            assert!(positions(&simplified) * 2 < positions(&topology));
+            for (arc, simplified_arc) in
+                presimplified.arcs.iter().zip(&simplified.arcs)
+            {
+                assert_eq!(simplified_arc[0], arc[0][..2]);
+                assert_eq!(
+                    simplified_arc[simplified_arc.len() - 1],
+                    arc[arc.len() - 1][..2]
+                );
+            }
+
+            match feature_from_name::<f64>(&simplified, "land") {
+                Some(Geometry::GeometryCollection(GeometryCollection(v))) => {
+                    assert!(
+                        matches!(&v[0], Geometry::MultiPolygon(mp) if mp.0.len() == 1428)
+                    );
+                }
+                _ => panic!("land should decode to a collection"),
+            }
+        }
+    }
+
+    /// Asserts that small islands are removed, while small countries which
+    /// share a border are protected.
+    #[test]
+    pub fn filtered_world() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+
+        // About 400 square kilometres.
+        let min_area = 1e-5_f64;
+        let land_polygons =
+            |t: &Topology| match feature_from_name::<f64>(t, "land") {
+                Some(Geometry::GeometryCollection(GeometryCollection(v))) => {
+                    match &v[0] {
+                        Geometry::MultiPolygon(mp) => mp.0.len(),
+                        _ => panic!("land should hold a multipolygon"),
+                    }
+                }
+                _ => panic!("land should decode to a collection"),
+            };
+
+        let filtered = filter(
+            &topology,
+            filter_weight(&topology, min_area, spherical_ring_area),
+        );
+        let remaining = land_polygons(&filtered);
+        assert!(0 < remaining && remaining < 1428 / 2, "{remaining}");
+        // The arcs of the removed rings are pruned.
+        assert!(filtered.arcs.len() < topology.arcs.len());
+
+        // Every ring of "countries" also appears in "land", keep only the
+        // countries so that islands are not seen as attached.
+        let mut countries = topology.clone();
+        countries.objects.retain(|o| o.name == "countries");
+        let attached = filter_attached(&countries);
+        let weight = filter_weight(&countries, min_area, spherical_ring_area);
+        let filtered = filter(&countries, |ring, interior| {
+            attached(ring, interior) || weight(ring, interior)
+        });
+        let ids: Vec<serde_json::Value> =
+            features_iter::<f64>(&filtered, "countries")
+                .expect("countries should be present.")
+                .filter_map(|f| f.id)
+                .collect();
+        // Andorra shares its borders, Nauru is an island.
+        assert!(ids.contains(&serde_json::json!("020")));
+        assert!(!ids.contains(&serde_json::json!("520")));
+    }
+
+    /// Asserts that a topology built from the decoded countries decodes to
+    /// the same countries, and that shared borders are stored once.
+    #[test]
+    pub fn rebuilt_countries() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let world: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+        let countries = feature_from_name::<f64>(&world, "countries")
+            .expect("countries should be present.");
+
+        // Rings may be rotated to start at a junction.
+        fn normalize(g: &Geometry) -> Vec<Vec<(f64, f64)>> {
+            let mut rings = vec![];
+            let mut add = |ls: &geo::LineString| {
+                let mut ring: Vec<(f64, f64)> =
+                    ls.0[..ls.0.len() - 1].iter().map(|c| c.x_y()).collect();
+                let min = (0..ring.len())
+                    .min_by(|a, b| ring[*a].partial_cmp(&ring[*b]).unwrap())
+                    .unwrap_or(0);
+                ring.rotate_left(min);
+                rings.push(ring);
+            };
+            match g {
+                Geometry::GeometryCollection(gc) => {
+                    for g in gc {
+                        rings.extend(normalize(g));
+                    }
+                }
+                Geometry::Polygon(p) => {
+                    add(p.exterior());
+                    p.interiors().iter().for_each(&mut add);
+                }
+                Geometry::MultiPolygon(mp) => {
+                    for p in mp {
+                        add(p.exterior());
+                        p.interiors().iter().for_each(&mut add);
+                    }
+                }
+                _ => panic!("countries should be polygonal"),
+            }
+            rings
+        }
+
+        let rebuilt =
+            topology(vec![("countries".to_string(), countries.clone())], None);
+        assert!(validate(&rebuilt).is_empty());
+        assert!(rebuilt.arcs.len() <= world.arcs.len());
+        let decoded = feature_from_name::<f64>(&rebuilt, "countries")
+            .expect("countries should be present.");
+        assert_eq!(normalize(&decoded), normalize(&countries));
+
+        let quantized =
+            topology(vec![("countries".to_string(), countries)], Some(10_000));
+        // A few tiny islands collapse onto a single grid point.
+        let issues = validate(&quantized);
+        assert!(issues.len() < 5);
+        assert!(issues.iter().all(|i| i.kind == IssueKind::ShortRing));
+        assert!(quantized.transform.is_some());
+    }
+
+    /// Asserts that a single country can be extracted, with only its arcs.
+    #[test]
+    pub fn pruned_country() {
+        let file = File::open("./tests/world-atlas/world/50m.json")
+            .expect("File should be readable.");
+        let mut topology: Topology = serde_json::from_reader(file)
+            .expect("File should be parse as JSON.");
+        let andorra = serde_json::json!("020");
+        let country = |t: &Topology| {
+            features_iter::<f64>(t, "countries")
+                .expect("countries should be present.")
+                .find(|f| f.id.as_ref() == Some(&andorra))
+                .expect("Andorra should be present.")
+                .geometry
+        };
+        let expected = country(&topology);
+
+        topology.objects.retain(|o| o.name == "countries");
+        if let Value::GeometryCollection(gc) =
+            &mut topology.objects[0].geometry.value
+        {
+            gc.retain(|g| g.id.as_ref() == Some(&andorra));
+        }
+        prune(&mut topology);
+
+        // Andorra borders France and Spain.
+        assert_eq!(topology.arcs.len(), 2);
+        assert!(validate(&topology).is_empty());
+        assert_eq!(country(&topology), expected);
+    }
 }
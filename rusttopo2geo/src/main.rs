@@ -3,6 +3,7 @@ extern crate rust_topojson_client;
 
 use std::fs::File;
 use std::io;
+use std::io::BufReader;
 use std::io::prelude::*;
 
 use clap::Arg;
@@ -10,9 +11,10 @@ use clap::Command;
 use geo::CoordNum;
 use geo::Geometry;
 use serde::Serialize;
-use topojson::Topology;
 
-use rust_topojson_client::feature::feature_from_name;
+use rust_topojson_client::feature::try_feature_flat;
+use rust_topojson_client::load::FlatTopology;
+use rust_topojson_client::load::{load, load_objects};
 
 fn main() -> io::Result<()> {
     let matches = Command::new("topo2geo")
@@ -45,52 +47,56 @@ fn main() -> io::Result<()> {
 
     let filename = matches.get_one::<String>("INPUT");
 
-    let topo = read(filename.map(|x| &**x))?;
+    let name = "countries";
 
     if matches.contains_id("LIST") {
+        let topo = read(filename.map(|x| &**x), None)?;
         write_list(&topo);
     } else if matches.contains_id("INPUT") {
-        write(&topo)?
+        let topo = read(filename.map(|x| &**x), Some(&[name]))?;
+        write(&topo, name)?
     }
     Ok(())
 }
 
-fn read(filename: Option<&str>) -> io::Result<Topology> {
-    let mut buffer = String::new();
-    match filename {
+/// Streams the topology, keeping only the named objects when given.
+fn read(
+    filename: Option<&str>,
+    names: Option<&[&str]>,
+) -> io::Result<FlatTopology> {
+    let load_from = |reader: Box<dyn Read>| match names {
+        Some(names) => load_objects(reader, names),
+        None => load(reader),
+    };
+    let topo = match filename {
         Some(filename) => {
-            let mut f = File::open(filename)?;
-            f.read_to_string(&mut buffer).expect("failed to read input");
-        }
-        None => {
-            io::stdin().read_to_string(&mut buffer)?;
+            load_from(Box::new(BufReader::new(File::open(filename)?)))
         }
-    }
+        None => load_from(Box::new(io::stdin().lock())),
+    };
 
-    let t = serde_json::from_str(&buffer).expect("Failed to parse");
-    Ok(t)
+    topo.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
 
-fn write_list(topo: &Topology) {
+fn write_list(topo: &FlatTopology) {
     for ng in &topo.objects {
         println!("\t{}", ng.name);
     }
 }
 
-fn write(topo: &Topology) -> io::Result<()> {
-    let name = "countries";
-
-    let has_object = topo.objects.iter().any(|ng| ng.name == name);
-    println!("has_object {has_object}");
-    if !has_object {
-        panic!("error: object {name} not found")
-    }
-
-    if let Some(feature) = feature_from_name::<f64>(topo, name) {
-        //TODO refactor of newlinedelited option
-        println!("about to write");
-        write_feature("out.txt", &feature)?;
-    }
+fn write(topo: &FlatTopology, name: &str) -> io::Result<()> {
+    let Some(ng) = topo.object(name) else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("error: object {name} not found"),
+        ));
+    };
+
+    let feature = try_feature_flat::<f64>(topo, &ng.geometry.value)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    //TODO refactor of newlinedelited option
+    println!("about to write");
+    write_feature("out.txt", &feature)?;
 
     // Signal nothing to write.
     Ok(())